@@ -0,0 +1,100 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+const USAGE: &str = "Usage: hort_mc [options]
+  --camera-path <file>    Camera path to record to and play back, default camera_path.txt
+  --play-camera-path      Start playing the camera path straight away
+  --output <file>         Render one image to a .png or .exr file without opening a window
+  --width <pixels>        Width of the rendered image, default 900
+  --height <pixels>       Height of the rendered image, default 900
+  --samples <count>       Samples per pixel averaged for the rendered image, default 64
+  --time <seconds>        Render the camera path at this time instead of the default camera
+  --frames <directory>    Render the camera path as a numbered PNG sequence without a window
+  --video <file>          Render the camera path to a video by piping frames into ffmpeg
+  --fps <rate>            Frames per second of simulated time when exporting, default 30
+  --still-scale <n>       High res stills are n times the window resolution, default 4
+  --still-samples <count> Samples per pixel for high res stills, default 256
+  --dev-shaders           Load shaders from the source tree and reload them when they change
+  --shader-cache          Keep compiled shaders in the user's cache directory to start faster
+  --gl-info               Show the OpenGL driver's version, limits and extensions
+  --help                  Show this message";
+
+/// Command line options.
+#[derive(Debug, Clone)]
+pub struct Arguments {
+    pub camera_path: PathBuf,
+    pub play_camera_path: bool,
+    pub output: Option<PathBuf>,  // Render headless to this file instead of running the game
+    pub width: u32,
+    pub height: u32,
+    pub samples: u32,
+    pub time: Option<f32>,
+    pub frames: Option<PathBuf>,  // Export the camera path as a PNG sequence to this directory
+    pub video: Option<PathBuf>,  // Or encode it to this file
+    pub fps: f32,
+    pub still_scale: u32,
+    pub still_samples: u32,
+    pub dev_shaders: bool,
+    pub shader_cache: bool,
+    pub gl_info: bool,
+}
+
+impl Arguments {
+    /// Parses the process's arguments, exiting with the usage message if they are invalid.
+    pub fn from_env() -> Arguments {
+        match Arguments::parse(std::env::args().skip(1)) {
+            Ok(arguments) => arguments,
+            Err(error) => {
+                if !error.is_empty() {
+                    eprintln!("{}", error);
+                }
+                eprintln!("{}", USAGE);
+                std::process::exit(if error.is_empty() { 0 } else { 1 });
+            }
+        }
+    }
+
+    /// An empty error means help was asked for.
+    pub fn parse(arguments: impl IntoIterator<Item = String>) -> Result<Arguments, String> {
+        let mut parsed = Arguments::default();
+        let mut arguments = arguments.into_iter();
+        while let Some(argument) = arguments.next() {
+            let mut value = |name: &str| arguments.next()
+                .ok_or_else(|| format!("Missing value for {}", name));
+            match argument.as_str() {
+                "--camera-path" => parsed.camera_path = PathBuf::from(value(&argument)?),
+                "--play-camera-path" => parsed.play_camera_path = true,
+                "--output" => parsed.output = Some(PathBuf::from(value(&argument)?)),
+                "--width" => parsed.width = parse_number(&argument, value(&argument)?)?,
+                "--height" => parsed.height = parse_number(&argument, value(&argument)?)?,
+                "--samples" => parsed.samples = parse_number(&argument, value(&argument)?)?,
+                "--time" => parsed.time = Some(parse_number(&argument, value(&argument)?)?),
+                "--frames" => parsed.frames = Some(PathBuf::from(value(&argument)?)),
+                "--video" => parsed.video = Some(PathBuf::from(value(&argument)?)),
+                "--fps" => parsed.fps = parse_number(&argument, value(&argument)?)?,
+                "--still-scale" => parsed.still_scale = parse_number(&argument, value(&argument)?)?,
+                "--still-samples" =>
+                    parsed.still_samples = parse_number(&argument, value(&argument)?)?,
+                "--dev-shaders" => parsed.dev_shaders = true,
+                "--shader-cache" => parsed.shader_cache = true,
+                "--gl-info" => parsed.gl_info = true,
+                "--help" | "-h" => return Err(String::new()),
+                _ => return Err(format!("Unknown argument \"{}\"", argument)),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+impl Default for Arguments {
+    fn default() -> Arguments {
+        Arguments { camera_path: PathBuf::from("camera_path.txt"), play_camera_path: false,
+            output: None, width: 900, height: 900, samples: 64, time: None, frames: None,
+            video: None, fps: 30.0, still_scale: 4, still_samples: 256,
+            dev_shaders: false, shader_cache: false, gl_info: false }
+    }
+}
+
+fn parse_number<T: FromStr>(name: &str, value: String) -> Result<T, String> {
+    value.parse().map_err(|_| format!("Invalid value \"{}\" for {}", value, name))
+}
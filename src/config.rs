@@ -0,0 +1,48 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+const APP_NAME: &str = "hort_mc";
+
+/// Where settings are kept, following the platform's conventions.
+pub fn config_dir() -> PathBuf {
+    if let Some(dir) = env::var_os("XDG_CONFIG_HOME") {
+        return PathBuf::from(dir).join(APP_NAME);
+    }
+    if let Some(dir) = env::var_os("APPDATA") {
+        return PathBuf::from(dir).join(APP_NAME);
+    }
+    if let Some(home) = env::var_os("HOME") {
+        return PathBuf::from(home).join(".config").join(APP_NAME);
+    }
+    return PathBuf::from(".").join(APP_NAME);
+}
+
+/// Reads a config file of `key = value` lines, lines starting with # are comments. A missing
+/// file is an error of kind NotFound, so callers can tell it apart from one with nothing set.
+pub fn load(name: &str) -> io::Result<HashMap<String, String>> {
+    let contents = fs::read_to_string(config_dir().join(name))?;
+    Ok(parse(&contents))
+}
+
+pub fn parse(contents: &str) -> HashMap<String, String> {
+    contents.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+/// Writes a config file in the format read by load, keeping the order given.
+pub fn save(name: &str, values: &[(&str, String)]) -> io::Result<()> {
+    let dir = config_dir();
+    fs::create_dir_all(&dir)?;
+
+    let contents: String = values.iter()
+        .map(|(key, value)| format!("{} = {}\n", key, value))
+        .collect();
+    fs::write(dir.join(name), contents)
+}
@@ -0,0 +1,95 @@
+use gl::types::GLsizei;
+use image::Rgba32FImage;
+
+use crate::graphics::{error_handler, sgl, Texture2D};
+use crate::graphics::backend::{Rect, RenderBackend, TextureFormat, TraceParams};
+use crate::graphics::headless::read_hdr_texture;
+use crate::graphics::shader_buffers::{Std430, StorageBuffer};
+use crate::graphics::screen_copier::texture_drawer::GlSurface;
+use crate::graphics::shader_utils::error::ShaderError;
+use crate::graphics::world_renderer::ray_tracer::{GlRayTraceKernel, RayTracerSettings};
+use crate::world::World;
+
+/// Renders with OpenGL 4.6 on the gpu. Everything goes through the current context, so there
+/// has to be one, e.g. from a Window or HeadlessContext.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GlBackend;
+
+impl RenderBackend for GlBackend {
+    type Texture = Texture2D;
+    type Buffer<T: Std430> = StorageBuffer<T>;
+    type RayTraceKernel = GlRayTraceKernel;
+    type Surface = GlSurface;
+
+    fn create_texture(&self, width: u32, height: u32, format: TextureFormat, label: &str) -> Texture2D {
+        let format = match format {
+            TextureFormat::Rgba8 => gl::RGBA8,
+            TextureFormat::Rgba32F => gl::RGBA32F,
+        };
+        let texture = Texture2D::new(width, height, format);
+        texture.set_label(label);
+        texture
+    }
+
+    fn texture_size(&self, texture: &Texture2D) -> (u32, u32) {
+        texture.size()
+    }
+
+    fn write_texture(&self, texture: &Texture2D, image: &Rgba32FImage) {
+        let image = image::imageops::flip_vertical(image);  // GL's first row is the bottom
+        texture.upload_rgba32f(image.as_raw());
+    }
+
+    fn read_texture(&self, texture: &Texture2D) -> Rgba32FImage {
+        read_hdr_texture(texture)  // Read as floats whatever the format
+    }
+
+    fn create_buffer<T: Std430>(&self, data: Vec<T>, label: &str) -> StorageBuffer<T> {
+        let mut buffer = StorageBuffer::new(data);
+        buffer.set_label(label);
+        buffer.flush();
+        buffer
+    }
+
+    fn read_buffer<T: Std430>(&self, buffer: &StorageBuffer<T>) -> Vec<T> {
+        buffer.read_back()
+    }
+
+    fn write_buffer<T: Std430>(&self, buffer: &mut StorageBuffer<T>, offset: usize, values: &[T]) {
+        buffer.write(offset, values);
+        buffer.flush();
+    }
+
+    fn load_ray_tracer(&self, settings: &RayTracerSettings, world: &World)
+                       -> Result<GlRayTraceKernel, ShaderError> {
+        GlRayTraceKernel::load(settings, world)
+    }
+
+    fn dispatch_ray_trace(&self, kernel: &mut GlRayTraceKernel, texture: &Texture2D, params: &TraceParams,
+                          tiles: &[Rect]) {
+        kernel.dispatch(texture, params, tiles);
+    }
+
+    fn surface_size(&self, surface: &GlSurface) -> (u32, u32) {
+        (surface.window.width, surface.window.height)
+    }
+
+    fn resize_surface(&self, surface: &mut GlSurface) -> (u32, u32) {
+        let size = surface.window.update_size();
+        sgl::Viewport(0, 0, size.0 as GLsizei, size.1 as GLsizei);
+        size
+    }
+
+    fn clear(&self, _surface: &mut GlSurface) {
+        sgl::Clear(gl::COLOR_BUFFER_BIT);
+    }
+
+    fn draw_texture(&self, surface: &mut GlSurface, texture: &Texture2D, area: Rect, blend: bool) {
+        surface.draw_quad(texture, area, blend);
+    }
+
+    fn present(&self, surface: &mut GlSurface) {
+        surface.window.window.gl_swap_window();
+        error_handler::check();
+    }
+}
@@ -0,0 +1,102 @@
+use image::Rgba32FImage;
+
+use crate::graphics::shader_buffers::Std430;
+use crate::graphics::shader_utils::error::ShaderError;
+use crate::graphics::world_renderer::camera::Camera;
+use crate::graphics::world_renderer::ray_tracer::RayTracerSettings;
+use crate::world::World;
+
+pub mod gl_backend;
+pub mod software;
+
+pub use gl_backend::GlBackend;
+pub use software::{SoftwareBackend, SoftwareBuffer, SoftwareSurface, SoftwareTexture};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureFormat {
+    Rgba8,
+    Rgba32F,
+}
+
+/// An area of a texture or surface in pixels, from the bottom left as in GL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Rect {
+        Rect { x, y, width, height }
+    }
+
+    /// All of something size big.
+    pub fn covering((width, height): (u32, u32)) -> Rect {
+        Rect { x: 0, y: 0, width, height }
+    }
+}
+
+/// The inputs of ray_tracer.comp, which are the same for every tile of a frame.
+#[derive(Debug, Clone, Copy)]
+pub struct TraceParams<'a> {
+    pub camera: &'a Camera,
+    pub world: &'a World,  // For backends without their own copy from load_ray_tracer
+    pub jitter: (f32, f32),  // Sub pixel offset of each ray, in pixels
+    pub sample_index: u32,  // Samples already in the texture to average with, 0 replaces them
+    pub seed: u32,
+}
+
+/// Everything the renderers need from the graphics api: creating textures and buffers, running
+/// the ray tracer over them and showing them. GlBackend does it on the gpu, SoftwareBackend on
+/// the cpu without a GL context, so RayTracer and TextureDrawer can be tested anywhere.
+pub trait RenderBackend {
+    type Texture;
+    /// An array of std430 elements for a storage block. Nothing dispatched reads these yet.
+    type Buffer<T: Std430>;
+    /// The compiled ray tracer, along with anything it keeps on the gpu such as the world.
+    type RayTraceKernel;
+    /// Where textures are drawn to and presented, e.g. a window.
+    type Surface;
+
+    fn create_texture(&self, width: u32, height: u32, format: TextureFormat, label: &str) -> Self::Texture;
+
+    fn texture_size(&self, texture: &Self::Texture) -> (u32, u32);
+
+    /// Replaces every pixel of the texture, from an image with the first row at the top.
+    fn write_texture(&self, texture: &Self::Texture, image: &Rgba32FImage);
+
+    /// Reads every pixel of the texture back, with the first row at the top.
+    fn read_texture(&self, texture: &Self::Texture) -> Rgba32FImage;
+
+    fn create_buffer<T: Std430>(&self, data: Vec<T>, label: &str) -> Self::Buffer<T>;
+
+    /// Every element of the buffer, including anything shaders stored to it.
+    fn read_buffer<T: Std430>(&self, buffer: &Self::Buffer<T>) -> Vec<T>;
+
+    /// Overwrites elements from offset, panicking if they'd go past the end. Anything dispatched
+    /// afterwards sees them.
+    fn write_buffer<T: Std430>(&self, buffer: &mut Self::Buffer<T>, offset: usize, values: &[T]);
+
+    fn load_ray_tracer(&self, settings: &RayTracerSettings, world: &World)
+                       -> Result<Self::RayTraceKernel, ShaderError>;
+
+    /// Traces each tile of an RGBA32F texture. Anything reading the texture afterwards sees the
+    /// result.
+    fn dispatch_ray_trace(&self, kernel: &mut Self::RayTraceKernel, texture: &Self::Texture,
+                          params: &TraceParams, tiles: &[Rect]);
+
+    fn surface_size(&self, surface: &Self::Surface) -> (u32, u32);
+
+    /// Call after the surface changed size, returns the new size.
+    fn resize_surface(&self, surface: &mut Self::Surface) -> (u32, u32);
+
+    fn clear(&self, surface: &mut Self::Surface);
+
+    /// Stretches texture over an area of the surface, alpha blended over what's there if blend.
+    fn draw_texture(&self, surface: &mut Self::Surface, texture: &Self::Texture, area: Rect, blend: bool);
+
+    /// Shows what was drawn since the last present.
+    fn present(&self, surface: &mut Self::Surface);
+}
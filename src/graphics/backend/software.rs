@@ -0,0 +1,212 @@
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use image::{Rgba, Rgba32FImage};
+
+use crate::graphics::backend::{Rect, RenderBackend, TextureFormat, TraceParams};
+use crate::graphics::shader_buffers::Std430;
+use crate::graphics::shader_utils::error::ShaderError;
+use crate::graphics::world_renderer::cpu_ray_tracer::trace_sample;
+use crate::graphics::world_renderer::ray_tracer::RayTracerSettings;
+use crate::maths::vector::Vec3;
+use crate::world::World;
+
+/// Renders on the cpu with the same maths as the shaders, needing no GL context. Ray tracing
+/// gives the same image as CpuRayTracer, so it's as slow, and max_bounces and the workgroup
+/// size are ignored.
+#[derive(Debug, Clone, Default)]
+pub struct SoftwareBackend {
+    pub threads: Option<usize>,  // For ray tracing, None to use every core
+}
+
+/// Pixels kept as RGBA floats whatever the format, first row at the bottom as in GL. Rgba8
+/// textures are quantised when written to.
+#[derive(Debug)]
+pub struct SoftwareTexture {
+    pub label: String,
+    format: TextureFormat,
+    image: RefCell<Rgba32FImage>,
+}
+
+impl SoftwareTexture {
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+}
+
+/// The elements, kept as they are since there's no gpu to upload them to.
+#[derive(Debug, Clone)]
+pub struct SoftwareBuffer<T: Std430> {
+    pub label: String,
+    data: Vec<T>,
+}
+
+/// An image standing in for a window. Drawing goes to a back buffer and present copies it to
+/// what's been presented.
+#[derive(Debug, Clone)]
+pub struct SoftwareSurface {
+    back: Rgba32FImage,  // First row at the bottom
+    presented: Rgba32FImage,  // First row at the top, like a screenshot
+    frames: u32,
+}
+
+impl SoftwareSurface {
+    pub fn new(width: u32, height: u32) -> SoftwareSurface {
+        SoftwareSurface { back: Rgba32FImage::new(width, height), presented: Rgba32FImage::new(width, height),
+            frames: 0 }
+    }
+
+    /// Changes the size, clearing it. Follow with TextureDrawer::update_size like a window.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        *self = SoftwareSurface { frames: self.frames, ..SoftwareSurface::new(width, height) };
+    }
+
+    /// What was drawn before the last present.
+    pub fn presented(&self) -> &Rgba32FImage {
+        &self.presented
+    }
+
+    /// How many times it's been presented.
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+}
+
+impl RenderBackend for SoftwareBackend {
+    type Texture = SoftwareTexture;
+    type Buffer<T: Std430> = SoftwareBuffer<T>;
+    type RayTraceKernel = ();
+    type Surface = SoftwareSurface;
+
+    fn create_texture(&self, width: u32, height: u32, format: TextureFormat, label: &str) -> SoftwareTexture {
+        SoftwareTexture { label: label.to_string(), format, image: RefCell::new(Rgba32FImage::new(width, height)) }
+    }
+
+    fn texture_size(&self, texture: &SoftwareTexture) -> (u32, u32) {
+        texture.image.borrow().dimensions()
+    }
+
+    fn write_texture(&self, texture: &SoftwareTexture, image: &Rgba32FImage) {
+        assert_eq!(image.dimensions(), self.texture_size(texture), "Wrong number of pixels");
+        let mut flipped = image::imageops::flip_vertical(image);
+        if texture.format == TextureFormat::Rgba8 {
+            for value in flipped.iter_mut() {
+                *value = (value.clamp(0.0, 1.0) * 255.0).round() / 255.0;
+            }
+        }
+        *texture.image.borrow_mut() = flipped;
+    }
+
+    fn read_texture(&self, texture: &SoftwareTexture) -> Rgba32FImage {
+        image::imageops::flip_vertical(&*texture.image.borrow())
+    }
+
+    fn create_buffer<T: Std430>(&self, data: Vec<T>, label: &str) -> SoftwareBuffer<T> {
+        SoftwareBuffer { label: label.to_string(), data }
+    }
+
+    fn read_buffer<T: Std430>(&self, buffer: &SoftwareBuffer<T>) -> Vec<T> {
+        buffer.data.clone()
+    }
+
+    fn write_buffer<T: Std430>(&self, buffer: &mut SoftwareBuffer<T>, offset: usize, values: &[T]) {
+        buffer.data[offset..offset + values.len()].copy_from_slice(values);
+    }
+
+    fn load_ray_tracer(&self, _settings: &RayTracerSettings, _world: &World) -> Result<(), ShaderError> {
+        Ok(())  // Nothing to compile, and the world comes with each dispatch
+    }
+
+    /// Runs ray_tracer.comp's invocations over the tiles a row at a time, on as many threads as
+    /// there are rows to share.
+    fn dispatch_ray_trace(&self, _kernel: &mut (), texture: &SoftwareTexture, params: &TraceParams,
+                          tiles: &[Rect]) {
+        let size = self.texture_size(texture);
+        let rows: Vec<(Rect, u32)> = tiles.iter()
+            .flat_map(|tile| (tile.y..tile.y + tile.height).map(move |y| (*tile, y)))
+            .collect();
+        let threads = self.threads
+            .unwrap_or_else(|| thread::available_parallelism().map_or(1, |threads| threads.get()))
+            .clamp(1, rows.len().max(1));
+
+        let pixels = Mutex::new(Vec::new());
+        let next_row = AtomicUsize::new(0);
+        {
+            let previous = texture.image.borrow();
+            let previous = &*previous;
+            thread::scope(|scope| {
+                for _ in 0..threads {
+                    scope.spawn(|| while let Some((tile, y)) = rows.get(next_row.fetch_add(1, Ordering::Relaxed)) {
+                        let row: Vec<_> = (tile.x..tile.x + tile.width).map(|x| {
+                            let seed = params.seed.wrapping_add(params.sample_index);
+                            let color = match trace_sample(params.camera, params.world, (x, *y), size,
+                                                           params.jitter, seed) {
+                                // Running average with the samples so far, as CpuRayTracer does it
+                                Some(color) if params.sample_index > 0 => {
+                                    let Rgba([red, green, blue, _]) = *previous.get_pixel(x, *y);
+                                    Vec3::new(red, green, blue).lerp(color, 1.0 / (params.sample_index + 1) as f32)
+                                }
+                                Some(color) => color,
+                                None => Vec3::ZERO,
+                            };
+                            (x, *y, color)
+                        }).collect();
+                        pixels.lock().unwrap().extend(row);
+                    });
+                }
+            });
+        }
+
+        let mut image = texture.image.borrow_mut();
+        for (x, y, color) in pixels.into_inner().unwrap() {
+            image.put_pixel(x, y, Rgba([color.x, color.y, color.z, 1.0]));
+        }
+    }
+
+    fn surface_size(&self, surface: &SoftwareSurface) -> (u32, u32) {
+        surface.back.dimensions()
+    }
+
+    fn resize_surface(&self, surface: &mut SoftwareSurface) -> (u32, u32) {
+        surface.back.dimensions()
+    }
+
+    fn clear(&self, surface: &mut SoftwareSurface) {
+        surface.back.pixels_mut().for_each(|pixel| *pixel = Rgba([0.0, 0.0, 0.0, 0.0]));
+    }
+
+    /// Samples the nearest texel to each pixel's centre, as the GL texture drawer does when
+    /// magnifying.
+    fn draw_texture(&self, surface: &mut SoftwareSurface, texture: &SoftwareTexture, area: Rect, blend: bool) {
+        let source = texture.image.borrow();
+        let (source_width, source_height) = source.dimensions();
+        let (width, height) = surface.back.dimensions();
+        if source_width == 0 || source_height == 0 {
+            return;
+        }
+        for y in area.y..(area.y + area.height).min(height) {
+            for x in area.x..(area.x + area.width).min(width) {
+                let u = (x - area.x) as f32 + 0.5;
+                let v = (y - area.y) as f32 + 0.5;
+                let source_x = ((u / area.width as f32 * source_width as f32) as u32).min(source_width - 1);
+                let source_y = ((v / area.height as f32 * source_height as f32) as u32).min(source_height - 1);
+                let Rgba(color) = *source.get_pixel(source_x, source_y);
+
+                let pixel = surface.back.get_pixel_mut(x, y);
+                *pixel = if blend {  // SRC_ALPHA, ONE_MINUS_SRC_ALPHA
+                    let alpha = color[3];
+                    Rgba([0, 1, 2, 3].map(|channel| color[channel] * alpha + pixel.0[channel] * (1.0 - alpha)))
+                } else {
+                    Rgba(color)
+                };
+            }
+        }
+    }
+
+    fn present(&self, surface: &mut SoftwareSurface) {
+        surface.presented = image::imageops::flip_vertical(&surface.back);
+        surface.frames += 1;
+    }
+}
@@ -0,0 +1,125 @@
+use std::fmt;
+
+use gl::types::{GLenum, GLint, GLuint};
+
+use crate::graphics::sgl;
+
+/// The shaders are #version 460, so the gpu renderer needs GL 4.6.
+pub const GPU_RENDERER_VERSION: (u32, u32) = (4, 6);
+
+// Shown by --gl-info, only queried if the context has the version they came in
+const LIMITS: [(&str, GLenum, (u32, u32)); 12] = [
+    ("MAX_TEXTURE_SIZE", gl::MAX_TEXTURE_SIZE, (2, 0)),
+    ("MAX_COMBINED_TEXTURE_IMAGE_UNITS", gl::MAX_COMBINED_TEXTURE_IMAGE_UNITS, (2, 0)),
+    ("MAX_UNIFORM_BLOCK_SIZE", gl::MAX_UNIFORM_BLOCK_SIZE, (3, 1)),
+    ("MAX_UNIFORM_BUFFER_BINDINGS", gl::MAX_UNIFORM_BUFFER_BINDINGS, (3, 1)),
+    ("UNIFORM_BUFFER_OFFSET_ALIGNMENT", gl::UNIFORM_BUFFER_OFFSET_ALIGNMENT, (3, 1)),
+    ("MAX_IMAGE_UNITS", gl::MAX_IMAGE_UNITS, (4, 2)),
+    ("MAX_COMPUTE_WORK_GROUP_INVOCATIONS", gl::MAX_COMPUTE_WORK_GROUP_INVOCATIONS, (4, 3)),
+    ("MAX_COMPUTE_SHARED_MEMORY_SIZE", gl::MAX_COMPUTE_SHARED_MEMORY_SIZE, (4, 3)),
+    ("MAX_SHADER_STORAGE_BLOCK_SIZE", gl::MAX_SHADER_STORAGE_BLOCK_SIZE, (4, 3)),
+    ("MAX_SHADER_STORAGE_BUFFER_BINDINGS", gl::MAX_SHADER_STORAGE_BUFFER_BINDINGS, (4, 3)),
+    ("MAX_LABEL_LENGTH", gl::MAX_LABEL_LENGTH, (4, 3)),
+    ("MAX_DEBUG_GROUP_STACK_DEPTH", gl::MAX_DEBUG_GROUP_STACK_DEPTH, (4, 3)),
+];
+
+/// What the current GL context supports, read once after it's created.
+#[derive(Debug, Clone, Default)]
+pub struct GlCapabilities {
+    pub version: (u32, u32),
+    pub version_string: String,  // As the driver gives it, e.g. "4.6 (Core Profile) Mesa 24.0.5"
+    pub glsl_version: String,
+    pub vendor: String,
+    pub renderer: String,
+    pub extensions: Vec<String>,
+    pub limits: Vec<(&'static str, GLint)>,
+}
+
+impl GlCapabilities {
+    /// Queries the current context, which has to be at least GL 3.0.
+    pub fn detect() -> GlCapabilities {
+        let (mut major, mut minor): (GLint, GLint) = (0, 0);
+        sgl::GetIntegerv(gl::MAJOR_VERSION, &mut major);
+        sgl::GetIntegerv(gl::MINOR_VERSION, &mut minor);
+        let version = (major as u32, minor as u32);
+
+        let mut extension_count: GLint = 0;
+        sgl::GetIntegerv(gl::NUM_EXTENSIONS, &mut extension_count);
+        let extensions = (0..extension_count as GLuint).map(|index| sgl::GetStringi(gl::EXTENSIONS, index))
+            .collect();
+
+        let limits = LIMITS.iter()
+            .filter(|(_, _, since)| version >= *since)
+            .map(|(name, limit, _)| {
+                let mut value: GLint = 0;
+                sgl::GetIntegerv(*limit, &mut value);
+                (*name, value)
+            })
+            .collect();
+
+        GlCapabilities { version, version_string: sgl::GetString(gl::VERSION),
+            glsl_version: sgl::GetString(gl::SHADING_LANGUAGE_VERSION), vendor: sgl::GetString(gl::VENDOR),
+            renderer: sgl::GetString(gl::RENDERER), extensions, limits }
+    }
+
+    pub fn at_least(&self, major: u32, minor: u32) -> bool {
+        self.version >= (major, minor)
+    }
+
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.extensions.iter().any(|extension| extension == name)
+    }
+
+    /// One of the limits shown by --gl-info, None if the context's version doesn't have it.
+    pub fn limit(&self, name: &str) -> Option<GLint> {
+        self.limits.iter().find(|(limit, _)| *limit == name).map(|(_, value)| *value)
+    }
+
+    pub fn compute_shaders(&self) -> bool {
+        self.at_least(4, 3) || self.has_extension("GL_ARB_compute_shader")
+    }
+
+    /// glDebugMessageCallback, glObjectLabel and debug groups.
+    pub fn debug_output(&self) -> bool {
+        self.at_least(4, 3) || self.has_extension("GL_KHR_debug")
+    }
+
+    /// What the gpu renderer needs that this context doesn't have, empty if it can run.
+    pub fn missing_for_gpu_renderer(&self) -> Vec<String> {
+        let mut missing = Vec::new();
+        if !self.compute_shaders() {
+            missing.push(String::from("compute shaders"));
+        }
+        let (major, minor) = GPU_RENDERER_VERSION;
+        if !self.at_least(major, minor) {
+            missing.push(format!("OpenGL {}.{} for #version {}{}0 shaders", major, minor, major, minor));
+        }
+        missing
+    }
+}
+
+impl fmt::Display for GlCapabilities {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Vendor: {}", self.vendor)?;
+        writeln!(f, "Renderer: {}", self.renderer)?;
+        writeln!(f, "Version: {} ({}.{})", self.version_string, self.version.0, self.version.1)?;
+        writeln!(f, "GLSL version: {}", self.glsl_version)?;
+        let missing = self.missing_for_gpu_renderer();
+        if missing.is_empty() {
+            writeln!(f, "GPU renderer: supported")?;
+        } else {
+            writeln!(f, "GPU renderer: unsupported, needs {}", missing.join(" and "))?;
+        }
+
+        writeln!(f, "Limits:")?;
+        let width = self.limits.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+        for (name, value) in &self.limits {
+            writeln!(f, "  {:width$} {}", name, value, width = width)?;
+        }
+        write!(f, "Extensions ({}):", self.extensions.len())?;
+        for extension in &self.extensions {
+            write!(f, "\n  {}", extension)?;
+        }
+        Ok(())
+    }
+}
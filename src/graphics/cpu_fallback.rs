@@ -0,0 +1,68 @@
+use std::time::Instant;
+
+use image::{Rgba, Rgba32FImage, RgbaImage};
+
+use crate::graphics::{error_handler, Framebuffer, Texture2D};
+use crate::graphics::window::Window;
+use crate::graphics::world_renderer::camera::Camera;
+use crate::graphics::world_renderer::cpu_ray_tracer::CpuRayTracer;
+use crate::graphics::world_renderer::fly_controller::FlyController;
+use crate::input::bindings::InputBindings;
+use crate::input::Input;
+use crate::world::World;
+
+const PIXEL_SIZE: u32 = 4;  // Window pixels per traced pixel each way, to keep it interactive
+
+/// Clamps and sRGB encodes linear radiance, the cpu renderer's stand in for post processing.
+pub fn hdr_to_ldr(image: &Rgba32FImage) -> RgbaImage {
+    RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+        let Rgba([red, green, blue, alpha]) = *image.get_pixel(x, y);
+        let encode = |linear: f32| {
+            let linear = linear.clamp(0.0, 1.0);
+            let srgb = if linear <= 0.0031308 { linear * 12.92 } else { 1.055 * linear.powf(1.0 / 2.4) - 0.055 };
+            (srgb * 255.0).round() as u8
+        };
+        Rgba([encode(red), encode(green), encode(blue), (alpha.clamp(0.0, 1.0) * 255.0).round() as u8])
+    })
+}
+
+/// Runs the game with the cpu ray tracer, for drivers without what the gpu renderer needs. Only
+/// flying around is supported, the image is shown at a low resolution and not post processed.
+pub fn mainloop(mut window: Window, camera: Camera, world: World) {
+    let mut ray_tracer = CpuRayTracer::new(camera, Default::default());
+    let mut fly_controller = FlyController::default();
+    let mut input = Input::new(&window.sdl, InputBindings::load());
+    input.set_mouse_captured(true);
+
+    let framebuffer = Framebuffer::new();
+    let mut texture: Option<Texture2D> = None;
+    let mut last_frame = Instant::now();
+    loop {
+        let delta_time = last_frame.elapsed().as_secs_f32();
+        last_frame = Instant::now();
+
+        let state = input.update();
+        if state.quit_requested() {
+            break;
+        }
+        if state.window_resized() {
+            window.update_size();
+        }
+        fly_controller.update(&mut ray_tracer.camera, state, delta_time);
+
+        let size = ((window.width / PIXEL_SIZE).max(1), (window.height / PIXEL_SIZE).max(1));
+        if texture.as_ref().is_none_or(|texture| texture.size() != size) {
+            texture = Some(Texture2D::new(size.0, size.1, gl::RGBA8));
+        }
+        let texture = texture.as_ref().unwrap();
+
+        let mut image = hdr_to_ldr(&ray_tracer.render(&world, size.0, size.1, 1));
+        image::imageops::flip_vertical_in_place(&mut image);  // GL's first row is the bottom
+        texture.upload_rgba8(&image);
+        framebuffer.blit_to_window(texture, (window.width, window.height));
+        window.window.gl_swap_window();
+        error_handler::check();
+    }
+
+    window.save_geometry();
+}
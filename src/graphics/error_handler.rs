@@ -1,52 +1,192 @@
-use std::ffi::{c_void, CStr, CString};
-use std::ptr;
+use std::backtrace::Backtrace;
+use std::collections::BTreeMap;
+use std::ffi::{c_void, CStr};
+use std::slice;
+use std::sync::Mutex;
 
 use gl::types::{GLchar, GLenum, GLsizei, GLuint};
+use log::Level;
 
 use crate::graphics::sgl;
 
+const LOG_TARGET: &str = "gl";
+const MAX_TRACKED_MESSAGES: usize = 1000;  // Forgets what's been seen past this, so it can't grow forever
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Notification,
+    Low,
+    Medium,
+    High,
+}
+
+impl Severity {
+    const ALL: [Severity; 4] = [Severity::Notification, Severity::Low, Severity::Medium, Severity::High];
+
+    fn from_gl(severity: GLenum) -> Severity {
+        match severity {
+            gl::DEBUG_SEVERITY_HIGH => Severity::High,
+            gl::DEBUG_SEVERITY_MEDIUM => Severity::Medium,
+            gl::DEBUG_SEVERITY_LOW => Severity::Low,
+            _ => Severity::Notification,
+        }
+    }
+
+    fn gl(self) -> GLenum {
+        match self {
+            Severity::Notification => gl::DEBUG_SEVERITY_NOTIFICATION,
+            Severity::Low => gl::DEBUG_SEVERITY_LOW,
+            Severity::Medium => gl::DEBUG_SEVERITY_MEDIUM,
+            Severity::High => gl::DEBUG_SEVERITY_HIGH,
+        }
+    }
+
+    fn log_level(self) -> Level {
+        match self {
+            Severity::Notification => Level::Debug,
+            Severity::Low => Level::Info,
+            Severity::Medium => Level::Warn,
+            Severity::High => Level::Error,
+        }
+    }
+}
+
+/// Which GL debug messages are logged, and what happens on errors. Set with configure before
+/// the window is created.
+#[derive(Debug, Clone)]
+pub struct DebugOutputSettings {
+    pub min_severity: Severity,
+    pub ignored_sources: Vec<GLenum>,  // e.g. gl::DEBUG_SOURCE_SHADER_COMPILER
+    pub ignored_ids: Vec<GLuint>,  // Driver specific messages known to be noise
+    pub strict: bool,  // Make check panic on high severity errors, for tests
+}
+
+impl Default for DebugOutputSettings {
+    fn default() -> DebugOutputSettings {
+        DebugOutputSettings {
+            min_severity: Severity::Low,
+            // Compile errors are already reported by ShaderError, with the right file and line
+            ignored_sources: vec![gl::DEBUG_SOURCE_SHADER_COMPILER],
+            ignored_ids: Vec::new(),
+            strict: false,
+        }
+    }
+}
+
+static SETTINGS: Mutex<Option<DebugOutputSettings>> = Mutex::new(None);  // None for the defaults
+// The first high severity error in strict mode, with a backtrace, until check takes it
+static FIRST_ERROR: Mutex<Option<String>> = Mutex::new(None);
+// How many times each message has been seen, keyed by source, type, id and text
+static SEEN: Mutex<BTreeMap<(GLenum, GLenum, GLuint, String), u32>> = Mutex::new(BTreeMap::new());
+
+/// Sets up debug output for contexts created after this.
+pub fn configure(settings: DebugOutputSettings) {
+    *SETTINGS.lock().unwrap() = Some(settings);
+}
+
+/// Panics with the first high severity GL error since the last check, if strict mode caught one.
+/// Called after each frame, as panicking in the callback itself would abort.
+pub fn check() {
+    if let Some(error) = FIRST_ERROR.lock().unwrap().take() {
+        panic!("{}", error);
+    }
+}
+
 /// Creates an error handler for opengl errors, requires opengl to already by initialised.
+/// Messages are filtered by the driver, so ones we'd ignore cost nothing.
 pub fn setup_error_handler() {
+    // Leaked so the callback can read it without locking, one per context
+    let settings: &'static DebugOutputSettings =
+        Box::leak(Box::new(SETTINGS.lock().unwrap().clone().unwrap_or_default()));
     sgl::Enable(gl::DEBUG_OUTPUT);
-    sgl::DebugMessageCallback(Some(error), ptr::null());
+    if settings.strict {
+        // So the backtrace is from the call that caused the error
+        sgl::Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
+    }
+
+    sgl::DebugMessageControl(gl::DONT_CARE, gl::DONT_CARE, gl::DONT_CARE, false);
+    for severity in Severity::ALL.into_iter().filter(|severity| *severity >= settings.min_severity) {
+        sgl::DebugMessageControl(gl::DONT_CARE, gl::DONT_CARE, severity.gl(), true);
+    }
+    for source in &settings.ignored_sources {
+        sgl::DebugMessageControl(*source, gl::DONT_CARE, gl::DONT_CARE, false);
+    }
+
+    sgl::DebugMessageCallback(Some(debug_message), settings as *const DebugOutputSettings as *const c_void);
 }
 
-/// Prints opengl errors to the console.
-extern "system" fn error(source: GLenum, type_: GLenum, id: GLuint, severity: GLenum,
-                         _length: GLsizei, msg: *const GLchar, _data: *mut c_void) {
+/// Logs opengl debug messages, only the first time they're seen and then every power of ten
+/// repeats, so one per frame doesn't flood the log. In strict mode the first high severity
+/// error is also kept for check, as a panic here can't unwind through the driver.
+extern "system" fn debug_message(source: GLenum, type_: GLenum, id: GLuint, severity: GLenum,
+                                 length: GLsizei, msg: *const GLchar, data: *mut c_void) {
+    let settings = unsafe { &*(data as *const DebugOutputSettings) };
+    if settings.ignored_ids.contains(&id) {
+        return;
+    }
+
+    let message = unsafe {
+        if length >= 0 {
+            String::from_utf8_lossy(slice::from_raw_parts(msg as *const u8, length as usize)).into_owned()
+        } else {
+            CStr::from_ptr(msg).to_string_lossy().into_owned()
+        }
+    };
+    let message = message.trim_end_matches(['\0', '\n']);
+    let severity = Severity::from_gl(severity);
+
+    if settings.strict && severity == Severity::High && type_ == gl::DEBUG_TYPE_ERROR {
+        FIRST_ERROR.lock().unwrap().get_or_insert_with(|| {
+            format!("GL error from {} (id {}): {}\n{}", source_name(source), id, message,
+                    Backtrace::force_capture())
+        });
+    }
 
-    let source = match source {
+    let count = {
+        let mut seen = SEEN.lock().unwrap();
+        if seen.len() >= MAX_TRACKED_MESSAGES {
+            seen.clear();
+        }
+        let count = seen.entry((source, type_, id, message.to_string())).or_insert(0);
+        *count += 1;
+        *count
+    };
+    if !is_power_of_ten(count) {
+        return;
+    }
+    let repeats = if count > 1 { format!(" (seen {} times)", count) } else { String::new() };
+    log::log!(target: LOG_TARGET, severity.log_level(), "{} from {} (id {}): {}{}", type_name(type_),
+              source_name(source), id, message, repeats);
+}
+
+fn is_power_of_ten(mut number: u32) -> bool {
+    while number > 1 && number.is_multiple_of(10) {
+        number /= 10;
+    }
+    number == 1
+}
+
+fn source_name(source: GLenum) -> &'static str {
+    match source {
         gl::DEBUG_SOURCE_API => "API",
         gl::DEBUG_SOURCE_WINDOW_SYSTEM => "WINDOW SYSTEM",
         gl::DEBUG_SOURCE_SHADER_COMPILER => "SHADER COMPILER",
         gl::DEBUG_SOURCE_THIRD_PARTY => "THIRD PARTY",
         gl::DEBUG_SOURCE_APPLICATION => "APPLICATION",
-        gl::DEBUG_SOURCE_OTHER => "UNKNOWN",
         _ => "UNKNOWN"
-    };
+    }
+}
 
-    let type_ = match type_ {
+fn type_name(type_: GLenum) -> &'static str {
+    match type_ {
         gl::DEBUG_TYPE_ERROR => "ERROR",
         gl::DEBUG_TYPE_DEPRECATED_BEHAVIOR => "DEPRECATED BEHAVIOR",
         gl::DEBUG_TYPE_UNDEFINED_BEHAVIOR => "UNDEFINED BEHAVIOR",
         gl::DEBUG_TYPE_PORTABILITY => "PORTABILITY",
         gl::DEBUG_TYPE_PERFORMANCE => "PERFORMANCE",
-        gl::DEBUG_TYPE_OTHER => "OTHER",
         gl::DEBUG_TYPE_MARKER => "MARKER",
-        _ => "UNKNOWN"
-    };
-
-
-    let severity = match severity {
-        gl::DEBUG_SEVERITY_HIGH => "HIGH",
-        gl::DEBUG_SEVERITY_MEDIUM => "MEDIUM",
-        gl::DEBUG_SEVERITY_LOW => "LOW",
-        gl::DEBUG_SEVERITY_NOTIFICATION => "NOTIFICATION",
-        _ => "UNKNOWN"
-    };
-
-    unsafe {
-        let str = CStr::from_ptr(msg).to_str().unwrap();
-        println!("[{}] [CODE {}]  {} in {}: {}", severity, id, type_, source, str);
+        gl::DEBUG_TYPE_PUSH_GROUP => "PUSH GROUP",
+        gl::DEBUG_TYPE_POP_GROUP => "POP GROUP",
+        _ => "OTHER"
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,154 @@
+use std::path::Path;
+
+use image::{DynamicImage, ImageBuffer, Rgba32FImage, RgbaImage};
+
+use crate::arguments::Arguments;
+use crate::graphics::backend::RenderBackend;
+use crate::graphics::capabilities::GlCapabilities;
+use crate::graphics::cpu_fallback::hdr_to_ldr;
+use crate::graphics::error_handler;
+use crate::graphics::post_processing::post_processor::PostProcessor;
+use crate::graphics::screen_copier::upscaler::halton;
+use crate::graphics::world_renderer::camera::Camera;
+use crate::graphics::world_renderer::camera_path::CameraPath;
+use crate::graphics::world_renderer::cpu_ray_tracer::CpuRayTracer;
+use crate::graphics::world_renderer::ray_tracer::RayTracer;
+use crate::graphics::window::Window;
+use crate::graphics::Texture2D;
+use crate::world::World;
+
+pub const STILL_ADAPTATION_TIME: f32 = 1000.0;  // Long enough for auto exposure to fully adapt at once
+
+/// A GL context without a visible window, for rendering images offscreen.
+pub struct HeadlessContext {
+    window: Window,  // Kept so the context stays alive
+}
+
+impl HeadlessContext {
+    pub fn new() -> HeadlessContext {
+        HeadlessContext { window: Window::new_hidden("Headless") }
+    }
+
+    pub fn capabilities(&self) -> &GlCapabilities {
+        &self.window.capabilities
+    }
+}
+
+impl Default for HeadlessContext {
+    fn default() -> HeadlessContext {
+        HeadlessContext::new()
+    }
+}
+
+/// The ray tracer's output, both as it came out and after post processing.
+pub struct RenderedImage {
+    pub hdr: Rgba32FImage,  // Linear radiance, before exposure and tone mapping
+    pub ldr: RgbaImage,  // Post processed, ready to display
+}
+
+impl RenderedImage {
+    /// Saves the image in the format given by the path's extension. .exr files get the HDR
+    /// image, anything else the post processed one.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let is_exr = path.extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("exr"));
+        let result = if is_exr {
+            DynamicImage::ImageRgba32F(self.hdr.clone()).save(path)
+        } else {
+            self.ldr.save(path)
+        };
+        result.map_err(|error| format!("Failed to save {}: {}", path.display(), error))
+    }
+}
+
+/// Traces samples into texture and averages them, with each sample jittered inside its pixel
+/// so edges come out anti-aliased. Waits for every tile when rendering progressively.
+pub fn accumulate<B: RenderBackend>(ray_tracer: &mut RayTracer<B>, texture: &B::Texture, samples: u32) {
+    let previous_jitter = ray_tracer.jitter;
+    for sample in 0..samples.max(1) {
+        ray_tracer.jitter = if samples > 1 {
+            (halton(sample + 1, 2) - 0.5, halton(sample + 1, 3) - 0.5)
+        } else {
+            (0.0, 0.0)
+        };
+        ray_tracer.sample_index = sample;
+        while !ray_tracer.render_to(texture) {}
+    }
+    ray_tracer.jitter = previous_jitter;
+    ray_tracer.sample_index = 0;
+}
+
+/// Renders a still image at any resolution, independent of the window.
+pub fn render_image(ray_tracer: &mut RayTracer, post_processor: &mut PostProcessor, width: u32,
+                    height: u32, samples: u32) -> RenderedImage {
+    render_frame(ray_tracer, post_processor, (width, height), samples, STILL_ADAPTATION_TIME)
+}
+
+/// Renders one frame of a sequence, where delta_time is the simulated time since the last frame
+/// so auto exposure adapts as it would when playing.
+pub fn render_frame(ray_tracer: &mut RayTracer, post_processor: &mut PostProcessor,
+                    (width, height): (u32, u32), samples: u32, delta_time: f32) -> RenderedImage {
+    let texture = Texture2D::new(width, height, gl::RGBA32F);
+    let previous_camera = ray_tracer.camera.clone();
+    ray_tracer.camera.set_aspect_ratio(width, height);
+
+    accumulate(ray_tracer, &texture, samples);
+    let hdr = read_hdr_texture(&texture);
+    let ldr = read_ldr_texture(post_processor.process(&texture, delta_time));
+
+    ray_tracer.camera = previous_camera;
+    error_handler::check();
+    RenderedImage { hdr, ldr }
+}
+
+/// Reads back an RGBA32F texture, flipped so the first row is the top of the image.
+pub fn read_hdr_texture(texture: &Texture2D) -> Rgba32FImage {
+    let (width, height) = texture.size();
+    let mut image: Rgba32FImage = ImageBuffer::from_raw(width, height, texture.read_rgba32f()).unwrap();
+    image::imageops::flip_vertical_in_place(&mut image);
+    image
+}
+
+/// Reads back an RGBA8 texture, flipped so the first row is the top of the image.
+pub fn read_ldr_texture(texture: &Texture2D) -> RgbaImage {
+    let (width, height) = texture.size();
+    let mut image: RgbaImage = ImageBuffer::from_raw(width, height, texture.read_rgba8()).unwrap();
+    image::imageops::flip_vertical_in_place(&mut image);
+    image
+}
+
+/// Renders one image to the output file given on the command line without opening a window.
+/// The camera comes from the camera path at --time if given, otherwise the default camera. Uses
+/// the cpu ray tracer if the driver can't run the gpu one.
+pub fn render_to_file(arguments: &Arguments) -> Result<(), String> {
+    let output = arguments.output.as_ref().ok_or("No output file given")?;
+    if arguments.width == 0 || arguments.height == 0 {
+        return Err(String::from("The image needs a width and height of at least 1"));
+    }
+    let context = HeadlessContext::new();
+
+    let mut camera = Camera::default();
+    if let Some(time) = arguments.time {
+        let path = CameraPath::load(&arguments.camera_path)?;
+        let keyframe = path.sample(time)
+            .ok_or_else(|| format!("{} has no keyframes", arguments.camera_path.display()))?;
+        keyframe.apply(&mut camera);
+    }
+
+    let missing = context.capabilities().missing_for_gpu_renderer();
+    let image = if missing.is_empty() {
+        let mut ray_tracer = RayTracer::new(camera, World::demo(), Default::default());
+        let mut post_processor = PostProcessor::new(Default::default());
+        render_image(&mut ray_tracer, &mut post_processor, arguments.width, arguments.height, arguments.samples)
+    } else {
+        log::warn!("The GPU renderer needs {}, rendering with the CPU ray tracer instead", missing.join(" and "));
+        let hdr = CpuRayTracer::new(camera, Default::default())
+            .render(&World::demo(), arguments.width, arguments.height, arguments.samples);
+        RenderedImage { ldr: hdr_to_ldr(&hdr), hdr }
+    };
+    image.save(output)?;
+    println!("Saved {}x{} image with {} samples to {}", arguments.width, arguments.height,
+             arguments.samples, output.display());
+    Ok(())
+}
+
@@ -1,106 +1,294 @@
-use std::ffi::c_void;
 use std::thread;
 use std::time::{Duration, Instant};
 
-use gl::types::{GLint, GLsizei, GLuint};
-use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
+use gl::types::GLsizei;
+use sdl2::video::FullscreenType;
 
+use crate::arguments::Arguments;
+use crate::graphics::post_processing::post_processor::PostProcessor;
+use crate::graphics::profiler::{Profiler, GRAPH_HEIGHT, GRAPH_WIDTH};
 use crate::graphics::screen_copier::texture_drawer::TextureDrawer;
+use crate::graphics::screen_copier::upscaler::{UpscaleFilter, Upscaler};
+use crate::graphics::shader_utils::hot_reload::ShaderWatcher;
+use crate::graphics::window::Window;
+use crate::graphics::world_renderer::camera::Camera;
+use crate::graphics::world_renderer::camera_path::{CameraPath, CameraPathRecorder};
+use crate::graphics::world_renderer::fly_controller::FlyController;
 use crate::graphics::world_renderer::ray_tracer::RayTracer;
+use crate::input::action::Action;
+use crate::input::bindings::InputBindings;
+use crate::input::Input;
+use crate::world::World;
 
+pub mod backend;
+pub mod capabilities;
+pub mod cpu_fallback;
+pub mod headless;
+pub mod post_processing;
+pub mod profiler;
 pub mod screen_copier;
+pub mod screenshot;
+pub mod shader_buffers;
 mod sgl;
 mod window;
-mod shader_utils;
+pub mod shader_utils;
+pub mod video_export;
 pub mod world_renderer;
 pub mod error_handler;
 
-fn create_texture(width: GLuint, height: GLuint) -> GLuint {
-    let mut texture: GLuint = 0;
-    sgl::GenTextures(1, &mut texture);
-    sgl::BindTexture(gl::TEXTURE_2D, texture);
-    // set the texture wrapping parameters
-    sgl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
-    sgl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
-    // set texture filtering parameters
-    sgl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
-    sgl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as GLint);
-
-    sgl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGBA32F as GLint,
-                   width as GLsizei, height as GLsizei, 0, gl::RGBA, gl::FLOAT,
-                   0 as *const c_void);
-    return texture;
+pub use sgl::{BindGuard, Buffer, DebugGroup, Framebuffer, Query, Texture2D, TextureArray, VertexArray};
 
+/// Load shaders from the source tree instead of the binary, and reload them in the mainloop
+/// when they're saved.
+pub fn set_shader_dev_mode(enabled: bool) {
+    shader_utils::hot_reload::set_dev_mode(enabled);
 }
 
-fn update_texture_binding_point(texture: GLuint, binding: GLuint) {
-    sgl::ActiveTexture(gl::TEXTURE0 + binding);
-    sgl::BindTexture(gl::TEXTURE_2D, texture);
-    sgl::BindImageTexture(binding, texture, 0, gl::FALSE, 0, gl::READ_WRITE, gl::RGBA32F);
+/// Save linked shaders to the user's cache directory and load them from there next time. Off by
+/// default, and ignored in dev mode.
+pub fn set_shader_cache(enabled: bool) {
+    shader_utils::program_cache::set_enabled(enabled);
 }
 
-pub fn mainloop(mut texture_drawer: TextureDrawer, mut ray_tracer: RayTracer) {
-    let size = texture_drawer.get_size();
+/// Opens the game window and runs until it's closed, with the gpu renderer if the driver has
+/// what it needs and the cpu ray tracer otherwise.
+pub fn run(arguments: &Arguments) {
+    let window = Window::new("Game", 900, 900);
+    let missing = window.capabilities.missing_for_gpu_renderer();
+    if !missing.is_empty() {
+        log::warn!("The GPU renderer needs {}, which {} doesn't have. Falling back to the much slower CPU \
+                    ray tracer", missing.join(" and "), window.capabilities.renderer);
+        cpu_fallback::mainloop(window, Camera::default(), World::demo());
+        return;
+    }
+
+    let texture_drawer = TextureDrawer::from_window(window);
+    let ray_tracer = RayTracer::new(Default::default(), World::demo(), Default::default());
+    let post_processor = PostProcessor::new(Default::default());
+    let upscaler = Upscaler::new(1.0, UpscaleFilter::EdgeAdaptive);
+    mainloop(texture_drawer, ray_tracer, post_processor, upscaler, FlyController::default(), arguments);
+}
+
+/// Prints the driver's version, limits and extensions, for --gl-info.
+pub fn print_gl_info() {
+    let window = Window::new_hidden("GL info");
+    println!("{}", window.capabilities);
+}
+
+/// The texture the ray tracer renders into each frame, at the render resolution.
+fn trace_texture((width, height): (u32, u32)) -> Texture2D {
+    let texture = Texture2D::new(width, height, gl::RGBA32F);
+    texture.set_label("Ray traced image");
+    texture
+}
+
+fn load_camera_path(arguments: &Arguments) -> CameraPath {
+    if !arguments.camera_path.exists() {
+        return CameraPath::default();
+    }
+    match CameraPath::load(&arguments.camera_path) {
+        Ok(path) => path,
+        Err(error) => {
+            println!("{}", error);
+            CameraPath::default()
+        }
+    }
+}
+
+pub fn mainloop(mut texture_drawer: TextureDrawer, mut ray_tracer: RayTracer,
+                mut post_processor: PostProcessor, mut upscaler: Upscaler,
+                mut fly_controller: FlyController, arguments: &Arguments) {
+    let mut size = texture_drawer.get_size();
     sgl::Viewport(0, 0, size.0 as GLsizei, size.1 as GLsizei);
+    ray_tracer.camera.set_aspect_ratio(size.0, size.1);
 
-    let texture = create_texture(size.0, size.1);
+    let mut render_size = upscaler.render_size(size.0, size.1);
+    let mut texture = trace_texture(render_size);
 
-    let mut mouse_down = false;
-    let mut event_pump = texture_drawer.get_event_pump();
+    let mut input = Input::new(texture_drawer.sdl(), InputBindings::load());
+    input.set_mouse_captured(true);
 
-    let mut n_frames = 0;
-    let mut time = Instant::now();
-    let mut event_time: i128 = 0;
-    let mut render_time: i128 = 0;
-    let mut draw_time: i128 = 0;
+    let mut camera_path = load_camera_path(arguments);
+    let mut recorder: Option<CameraPathRecorder> = None;
+    let mut playback_time: Option<f32> = None;  // How far through the camera path we are
+    if arguments.play_camera_path && !camera_path.is_empty() {
+        playback_time = Some(0.0);
+    }
+
+    let mut profiler = Profiler::new();
+    let mut shader_watcher = shader_utils::hot_reload::dev_mode().then(ShaderWatcher::new);
+    let mut last_frame = Instant::now();
     'main: loop {
-        event_time -= time.elapsed().as_micros() as i128;
-        for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit { .. } => break 'main,
-                Event::MouseButtonDown { .. } => mouse_down=true,
-                Event::MouseButtonUp { .. } => mouse_down=false,
-                Event::KeyDown { keycode, .. }
-                    if keycode.unwrap()==Keycode::W => ray_tracer.camera.move_rel(0., 0., 1.),
-                Event::KeyDown { keycode, .. }
-                    if keycode.unwrap()==Keycode::A => ray_tracer.camera.move_rel(-1., 0., 0.),
-                Event::KeyDown { keycode, .. }
-                    if keycode.unwrap()==Keycode::S => ray_tracer.camera.move_rel(0., 0., -1.),
-                Event::KeyDown { keycode, .. }
-                    if keycode.unwrap()==Keycode::D => ray_tracer.camera.move_rel(1., 0., 0.),
-                Event::KeyDown { .. } => ray_tracer.camera.look_rel(0., 0., 15.),
-                Event::MouseMotion { xrel, yrel, .. } if mouse_down == true =>
-                    ray_tracer.camera.look_rel(xrel as f32, yrel as f32, 0.),
-                _ => {}
+        let delta_time = last_frame.elapsed().as_secs_f32();
+        last_frame = Instant::now();
+
+        profiler.begin_frame();
+        profiler.begin("event", false);
+        let state = input.update();
+        if state.quit_requested() {
+            break 'main;
+        }
+        if state.window_resized() {
+            size = texture_drawer.update_size();
+            ray_tracer.camera.set_aspect_ratio(size.0, size.1);
+        }
+        if state.just_pressed(Action::ToggleFullscreen) {
+            texture_drawer.toggle_fullscreen(FullscreenType::Desktop);
+        }
+        if state.just_pressed(Action::ToggleExclusiveFullscreen) {
+            texture_drawer.toggle_fullscreen(FullscreenType::True);
+        }
+        if state.just_pressed(Action::CycleToneMapper) {
+            let settings = &mut post_processor.settings;
+            settings.tone_mapper = settings.tone_mapper.next();
+            println!("Tone mapper: {:?}", settings.tone_mapper);
+        }
+        if state.just_pressed(Action::ToggleBloom) {
+            post_processor.settings.bloom = !post_processor.settings.bloom;
+            println!("Bloom: {}", post_processor.settings.bloom);
+        }
+        if state.just_pressed(Action::ToggleAutoExposure) {
+            post_processor.settings.auto_exposure = !post_processor.settings.auto_exposure;
+            println!("Auto exposure: {}", post_processor.settings.auto_exposure);
+        }
+        if state.just_pressed(Action::ToggleSrgb) {
+            post_processor.settings.srgb_output = !post_processor.settings.srgb_output;
+            println!("sRGB output: {}", post_processor.settings.srgb_output);
+        }
+        if state.just_pressed(Action::ExposureUp) || state.just_pressed(Action::ExposureDown) {
+            let step = if state.just_pressed(Action::ExposureUp) { 0.5 } else { -0.5 };
+            post_processor.settings.exposure_compensation += step;
+            println!("Exposure compensation: {} stops",
+                     post_processor.settings.exposure_compensation);
+        }
+        if state.just_pressed(Action::CycleUpscaleFilter) {
+            upscaler.filter = upscaler.filter.next();
+            println!("Upscale filter: {:?}", upscaler.filter);
+        }
+        if state.just_pressed(Action::RenderScaleUp) || state.just_pressed(Action::RenderScaleDown) {
+            let step = if state.just_pressed(Action::RenderScaleUp) { 0.1 } else { -0.1 };
+            upscaler.render_scale = (upscaler.render_scale + step).clamp(0.1, 1.0);
+            println!("Render scale: {:.1}", upscaler.render_scale);
+        }
+        if state.just_pressed(Action::CycleProjection) {
+            let projection = ray_tracer.camera.projection().next();
+            ray_tracer.camera.set_projection(projection);
+            println!("Projection: {:?}", projection);
+        }
+        if state.just_pressed(Action::RecordCameraPath) {
+            match recorder.take() {
+                Some(finished) => {
+                    camera_path = finished.finish(&ray_tracer.camera);
+                    match camera_path.save(&arguments.camera_path) {
+                        Ok(()) => println!("Saved camera path to {}", arguments.camera_path.display()),
+                        Err(error) => println!("Failed to save camera path: {}", error),
+                    }
+                }
+                None => {
+                    playback_time = None;
+                    recorder = Some(CameraPathRecorder::new(&ray_tracer.camera));
+                    println!("Recording camera path");
+                }
             }
         }
-        event_time += time.elapsed().as_micros() as i128;
+        if state.just_pressed(Action::PlayCameraPath) && recorder.is_none() {
+            playback_time = match playback_time {
+                None if !camera_path.is_empty() => Some(0.0),
+                _ => None,
+            };
+        }
 
-        render_time -= time.elapsed().as_micros() as i128;
-        ray_tracer.render_to(texture, size.0, size.1);
-        sgl::Finish();  // For timer
-        render_time += time.elapsed().as_micros() as i128;
-        draw_time -= time.elapsed().as_micros() as i128;
-        texture_drawer.draw(texture);
-        draw_time += time.elapsed().as_micros() as i128;
+        match playback_time {
+            Some(time) => {
+                fly_controller.stop();
+                if let Some(keyframe) = camera_path.sample(time) {
+                    keyframe.apply(&mut ray_tracer.camera);
+                }
+                playback_time = Some(time + delta_time).filter(|time| *time <= camera_path.duration());
+            }
+            None => fly_controller.update(&mut ray_tracer.camera, state, delta_time),
+        }
+        if let Some(recorder) = &mut recorder {
+            recorder.update(&ray_tracer.camera, delta_time);
+        }
 
-        thread::sleep(Duration::from_millis(10));
-        n_frames += 1;
-        if time.elapsed().as_secs() >= 2 {
+        if state.just_pressed(Action::ToggleProfiler) {
+            profiler.show_graph = !profiler.show_graph;
+        }
+        if state.just_pressed(Action::ExportProfile) {
+            match profiler.export() {
+                Ok(path) => println!("Saved profile to {}", path.display()),
+                Err(error) => println!("{}", error),
+            }
+        }
+        if shader_watcher.as_mut().is_some_and(|watcher| watcher.changed()) {
+            let results = [ray_tracer.reload_shaders(), upscaler.reload_shaders(),
+                           post_processor.reload_shaders(), texture_drawer.reload_shaders()];
+            let errors: Vec<_> = results.into_iter().filter_map(Result::err).collect();
+            if errors.is_empty() {
+                println!("Reloaded shaders");
+            }
+            for error in errors {
+                println!("Failed to reload shaders, keeping the old ones:\n{}", error);
+            }
+        }
+        let take_screenshot = state.just_pressed(Action::Screenshot);
+        let take_still = state.just_pressed(Action::HighResStill);
+
+        let capture_mouse = state.just_pressed(Action::CaptureMouse);
+        let release_mouse = state.just_pressed(Action::ReleaseMouse);
+        if capture_mouse || release_mouse {
+            input.set_mouse_captured(capture_mouse);
+        }
 
-            println!("\n\nFPS: {}\nevents: {}s\nrender: {}s\ndraw:   {}s",
-                     n_frames as f64 / time.elapsed().as_secs_f64(),
-                     (event_time / n_frames) as f64 / (1000000.0),
-                     (render_time / n_frames) as f64 / (1000000.0),
-                     (draw_time / n_frames) as f64 / (1000000.0));
+        if upscaler.render_size(size.0, size.1) != render_size {
+            render_size = upscaler.render_size(size.0, size.1);
+            texture = trace_texture(render_size);
+        }
 
-            time = Instant::now();
-            n_frames = 0;
+        profiler.begin("trace", true);
+        ray_tracer.jitter = upscaler.jitter();
+        ray_tracer.render_to(&texture);
+        profiler.begin("denoise", true);  // Upscaling, which with the temporal filter also denoises
+        if ray_tracer.camera_moved() {
+            upscaler.reset_history();
+        }
+        let upscaled_texture = upscaler.upscale(&texture, size);
+        profiler.begin("post", true);
+        let output_texture = post_processor.process(upscaled_texture, delta_time);
+        profiler.end();
 
-            event_time = 0;
-            render_time = 0;
-            draw_time = 0;
+        if take_screenshot {
+            match screenshot::save_screenshot(output_texture) {
+                Ok(path) => println!("Saved screenshot to {}", path.display()),
+                Err(error) => println!("{}", error),
+            }
+        }
+        let output_texture = if take_still {
+            println!("Rendering {}x high res still with {} samples", arguments.still_scale,
+                     arguments.still_samples);
+            match screenshot::save_high_res_still(&mut ray_tracer, &mut post_processor,
+                                                  texture_drawer.capabilities(), size,
+                                                  arguments.still_scale, arguments.still_samples) {
+                Ok(path) => println!("Saved high res still to {}", path.display()),
+                Err(error) => println!("{}", error),
+            }
+            // The still used the post processor at another size, so redo this frame's output
+            post_processor.process(upscaled_texture, 0.0)
+        } else {
+            output_texture
         };
+        profiler.begin("present", true);
+        texture_drawer.draw(output_texture);
+        if profiler.show_graph {
+            let graph = profiler.graph_texture();
+            texture_drawer.draw_overlay(graph, 8, 8, GRAPH_WIDTH * 2, GRAPH_HEIGHT * 2);
+        }
+        texture_drawer.present();
+        profiler.end_frame();
+
+        thread::sleep(Duration::from_millis(10));
     }
+
+    texture_drawer.save_window_geometry();
 }
\ No newline at end of file
@@ -0,0 +1 @@
+pub mod post_processor;
\ No newline at end of file
@@ -0,0 +1,252 @@
+use std140::{boolean, float, int, uint};
+
+use crate::graphics::{sgl, DebugGroup, Texture2D};
+use crate::graphics::shader_buffers::{StorageBuffer, UniformBuffer};
+use crate::graphics::shader_utils::{compute_program, require_interface};
+use crate::graphics::shader_utils::error::ShaderError;
+use crate::graphics::shader_utils::hot_reload::shader_file;
+use crate::graphics::shader_utils::program::Program;
+
+const HISTOGRAM_BINS: usize = 256;
+const WORKGROUP_SIZE: u32 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToneMapper {
+    None,
+    Reinhard,
+    Aces,
+    AgX,
+}
+
+impl ToneMapper {
+    /// The next tone mapper, for cycling through them at runtime.
+    pub fn next(self) -> ToneMapper {
+        match self {
+            ToneMapper::None => ToneMapper::Reinhard,
+            ToneMapper::Reinhard => ToneMapper::Aces,
+            ToneMapper::Aces => ToneMapper::AgX,
+            ToneMapper::AgX => ToneMapper::None,
+        }
+    }
+
+    fn shader_id(self) -> i32 {  // Matches the TONE_MAPPER_ defines in tone_map.comp
+        match self {
+            ToneMapper::None => 0,
+            ToneMapper::Reinhard => 1,
+            ToneMapper::Aces => 2,
+            ToneMapper::AgX => 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PostProcessSettings {
+    pub tone_mapper: ToneMapper,
+    pub auto_exposure: bool,
+    pub exposure: f32,  // Only used when auto_exposure is off
+    pub exposure_compensation: f32,  // In stops
+    pub min_log_luminance: f32,  // Range of the luminance histogram, in log2 units
+    pub max_log_luminance: f32,
+    pub adaptation_speed: f32,
+    pub bloom: bool,
+    pub bloom_threshold: f32,
+    pub bloom_strength: f32,
+    pub srgb_output: bool,
+}
+
+impl Default for PostProcessSettings {
+    fn default() -> PostProcessSettings {
+        PostProcessSettings {
+            tone_mapper: ToneMapper::Aces,
+            auto_exposure: true,
+            exposure: 1.0,
+            exposure_compensation: 0.0,
+            min_log_luminance: -8.0,
+            max_log_luminance: 4.0,
+            adaptation_speed: 1.5,
+            bloom: true,
+            bloom_threshold: 1.0,
+            bloom_strength: 0.1,
+            srgb_output: true,
+        }
+    }
+}
+
+#[std140::repr_std140]
+#[derive(Debug)]
+struct PostProcessBuffer {  // All values are duplicates of PostProcessSettings, plus per frame data
+    tone_mapper: int,
+    auto_exposure: boolean,
+    exposure: float,
+    exposure_compensation: float,
+    min_log_luminance: float,
+    log_luminance_range: float,
+    adaptation_speed: float,
+    delta_time: float,
+    pixel_count: uint,
+    bloom: boolean,
+    bloom_threshold: float,
+    bloom_strength: float,
+    srgb_output: boolean,
+}
+
+/// Turns the HDR output of the ray tracer into a displayable image, with auto exposure, bloom,
+/// tone mapping and sRGB encoding.
+pub struct PostProcessor {
+    pub settings: PostProcessSettings,
+
+    histogram_program: Program,
+    exposure_program: Program,
+    bloom_program: Program,
+    tone_map_program: Program,
+
+    settings_buffer: UniformBuffer<PostProcessBuffer>,  // Streamed, as delta_time changes every frame
+    histogram_buffer: StorageBuffer<u32>,
+    exposure_buffer: StorageBuffer<f32>,
+
+    size: (u32, u32),
+    bloom_textures: Vec<Texture2D>,  // Half resolution, ping-ponged between for the blur passes
+    output_texture: Option<Texture2D>,  // None until the first image is processed
+}
+
+impl PostProcessor {
+    pub fn new(settings: PostProcessSettings) -> PostProcessor {
+        let (histogram_program, exposure_program, bloom_program, tone_map_program) =
+            PostProcessor::load_shaders().unwrap_or_else(|error| panic!("{}", error));
+
+        let settings_buffer = UniformBuffer::streamed(PostProcessor::settings_data(&settings, 0.0, 0));
+        let histogram_buffer = StorageBuffer::new(vec![0; HISTOGRAM_BINS]);
+        // Start at middle grey so the first frames aren't black
+        let exposure_buffer = StorageBuffer::new(vec![0.18]);
+        settings_buffer.set_label("Post process settings");
+        histogram_buffer.set_label("Luminance histogram");
+        exposure_buffer.set_label("Exposure");
+
+        PostProcessor { settings, histogram_program, exposure_program, bloom_program,
+            tone_map_program, settings_buffer, histogram_buffer, exposure_buffer,
+            size: (0, 0), bloom_textures: Vec::new(), output_texture: None }
+    }
+
+    fn load_shaders() -> Result<(Program, Program, Program, Program), ShaderError> {
+        let defines = [("WORKGROUP_SIZE", WORKGROUP_SIZE.to_string())];
+        let bloom_file = shader_file!("bloom.comp");
+        Ok((compute_program(shader_file!("luminance_histogram.comp"), &defines)?,
+            compute_program(shader_file!("auto_exposure.comp"), &defines)?,
+            require_interface(compute_program(bloom_file, &defines)?, bloom_file,
+                              &[("bloom_pass", gl::INT)], &[])?,
+            compute_program(shader_file!("tone_map.comp"), &defines)?))
+    }
+
+    /// Recompiles the shaders, keeping the old ones if any fail to compile.
+    pub fn reload_shaders(&mut self) -> Result<(), ShaderError> {
+        (self.histogram_program, self.exposure_program, self.bloom_program, self.tone_map_program) =
+            PostProcessor::load_shaders()?;
+        Ok(())
+    }
+
+    /// (Re)creates the intermediate textures if the size of the image being processed changed.
+    fn resize(&mut self, width: u32, height: u32) {
+        if self.size == (width, height) {
+            return;
+        }
+        let bloom_width = (width / 2).max(1);
+        let bloom_height = (height / 2).max(1);
+        self.bloom_textures = (0..2).map(|index| {
+            let texture = Texture2D::new(bloom_width, bloom_height, gl::RGBA32F);
+            texture.set_filter(gl::LINEAR, gl::LINEAR);
+            texture.set_label(&format!("Bloom {}", index));
+            texture
+        }).collect();
+        let output_texture = Texture2D::new(width, height, gl::RGBA8);
+        output_texture.set_label("Post processed image");
+        self.output_texture = Some(output_texture);
+        self.size = (width, height);
+    }
+
+    fn settings_data(settings: &PostProcessSettings, delta_time: f32, pixel_count: u32) -> PostProcessBuffer {
+        PostProcessBuffer {
+            tone_mapper: int(settings.tone_mapper.shader_id()),
+            auto_exposure: settings.auto_exposure.into(),
+            exposure: float(settings.exposure),
+            exposure_compensation: float(settings.exposure_compensation),
+            min_log_luminance: float(settings.min_log_luminance),
+            log_luminance_range: float(settings.max_log_luminance - settings.min_log_luminance),
+            adaptation_speed: float(settings.adaptation_speed),
+            delta_time: float(delta_time),
+            pixel_count: uint(pixel_count),
+            bloom: settings.bloom.into(),
+            bloom_threshold: float(settings.bloom_threshold),
+            bloom_strength: float(settings.bloom_strength),
+            srgb_output: settings.srgb_output.into(),
+        }
+    }
+
+    /// Uploads the settings and points every pass's blocks at the buffers.
+    fn bind_buffers(&mut self, delta_time: f32) {
+        let pixel_count = self.size.0 * self.size.1;
+        self.settings_buffer.set(PostProcessor::settings_data(&self.settings, delta_time, pixel_count));
+        self.settings_buffer.flush();
+        self.histogram_buffer.flush();
+        self.exposure_buffer.flush();
+
+        let programs = [&mut self.histogram_program, &mut self.exposure_program, &mut self.bloom_program,
+                        &mut self.tone_map_program];
+        for program in programs {
+            // Passes only have the blocks they use, so missing ones are fine
+            let _ = program.bind_uniform_block("PostProcessBlock", self.settings_buffer.binding());
+            let _ = program.bind_storage_block("HistogramBlock", self.histogram_buffer.binding());
+            let _ = program.bind_storage_block("ExposureBlock", self.exposure_buffer.binding());
+        }
+    }
+
+    /// Runs every post processing pass on an HDR texture, returning an RGBA8 texture with the
+    /// final image. delta_time is in seconds and controls how quickly exposure adapts.
+    pub fn process(&mut self, hdr_texture: &Texture2D, delta_time: f32) -> &Texture2D {
+        let (width, height) = hdr_texture.size();
+        self.resize(width, height);
+        self.bind_buffers(delta_time);
+
+        if self.settings.auto_exposure {
+            let _group = DebugGroup::push("Auto exposure");
+            self.histogram_program.set_used();
+            hdr_texture.bind_image(0, gl::READ_ONLY);
+            sgl::DispatchCompute(groups(width), groups(height), 1);
+            sgl::MemoryBarrier(gl::SHADER_STORAGE_BARRIER_BIT);
+
+            self.exposure_program.set_used();
+            sgl::DispatchCompute(1, 1, 1);
+            sgl::MemoryBarrier(gl::SHADER_STORAGE_BARRIER_BIT);
+        }
+
+        if self.settings.bloom {
+            let _group = DebugGroup::push("Bloom");
+            self.bloom_program.set_used();
+            let (bloom_width, bloom_height) = ((width / 2).max(1), (height / 2).max(1));
+            let passes = [(0, hdr_texture, &self.bloom_textures[0]),
+                          (1, &self.bloom_textures[0], &self.bloom_textures[1]),
+                          (2, &self.bloom_textures[1], &self.bloom_textures[0])];
+            for (pass, input, output) in passes {
+                self.bloom_program.set_or_log("bloom_pass", pass);
+                let _input = input.bind(0);
+                output.bind_image(1, gl::WRITE_ONLY);
+                sgl::DispatchCompute(groups(bloom_width), groups(bloom_height), 1);
+                sgl::MemoryBarrier(gl::TEXTURE_FETCH_BARRIER_BIT);
+            }
+        }
+
+        let output_texture = self.output_texture.as_ref().unwrap();
+        let _group = DebugGroup::push("Tone mapping");
+        self.tone_map_program.set_used();
+        let _hdr = hdr_texture.bind(0);
+        let _bloom = self.bloom_textures[0].bind(1);
+        output_texture.bind_image(2, gl::WRITE_ONLY);
+        sgl::DispatchCompute(groups(width), groups(height), 1);
+        sgl::MemoryBarrier(gl::TEXTURE_FETCH_BARRIER_BIT | gl::SHADER_IMAGE_ACCESS_BARRIER_BIT);
+
+        return output_texture;
+    }
+}
+
+fn groups(pixels: u32) -> u32 {
+    pixels.div_ceil(WORKGROUP_SIZE)
+}
@@ -0,0 +1,263 @@
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use image::{Rgba, RgbaImage};
+
+use crate::graphics::screenshot::timestamp;
+use crate::graphics::{Query, Texture2D};
+
+const QUERY_FRAMES: usize = 4;  // Frames of queries in flight, so results are read without stalling
+const HISTORY_FRAMES: usize = 240;
+const PROFILE_DIR: &str = "profiles";
+
+pub const GRAPH_WIDTH: u32 = HISTORY_FRAMES as u32;  // One column per frame
+pub const GRAPH_HEIGHT: u32 = 100;
+const GRAPH_MAX_TIME: f32 = 1.0 / 30.0;  // Seconds at the top of the graph
+const GRAPH_BACKGROUND: Rgba<u8> = Rgba([0, 0, 0, 160]);
+const GRAPH_TARGET_LINE: Rgba<u8> = Rgba([255, 255, 255, 200]);  // At 60 fps
+const SCOPE_COLORS: [Rgba<u8>; 6] = [
+    Rgba([230, 80, 80, 230]), Rgba([80, 200, 90, 230]), Rgba([80, 140, 240, 230]),
+    Rgba([240, 200, 60, 230]), Rgba([200, 100, 230, 230]), Rgba([80, 220, 220, 230]),
+];
+
+/// Time spent in one named part of a frame.
+#[derive(Debug, Clone)]
+pub struct ScopeTiming {
+    pub name: &'static str,
+    pub cpu_start: Duration,  // Since the profiler was created
+    pub cpu_time: Duration,
+    pub gpu_time: Option<Duration>,  // None for scopes without gpu work, or until the result is in
+}
+
+#[derive(Debug, Clone)]
+pub struct FrameTiming {
+    pub frame: u64,
+    pub start: Duration,  // Since the profiler was created
+    pub cpu_time: Duration,
+    pub scopes: Vec<ScopeTiming>,
+}
+
+/// A frame whose gpu queries haven't all come back yet.
+#[derive(Debug)]
+struct PendingFrame {
+    timing: FrameTiming,
+    queries: Vec<Option<usize>>,  // Index into the query pool for each scope
+}
+
+/// Reusable GL_TIME_ELAPSED queries.
+#[derive(Debug, Default)]
+struct QueryPool {
+    queries: Vec<Query>,
+    free: Vec<usize>,
+}
+
+impl QueryPool {
+    fn take(&mut self) -> usize {
+        self.free.pop().unwrap_or_else(|| {
+            self.queries.push(Query::new(gl::TIME_ELAPSED));
+            self.queries.len() - 1
+        })
+    }
+
+    /// The query's time if the gpu has finished with it.
+    fn result(&self, index: usize) -> Option<Duration> {
+        self.queries[index].result().map(Duration::from_nanos)
+    }
+}
+
+/// Times named scopes of each frame on the cpu and, with timer queries, on the gpu. Results are
+/// read a few frames late so measuring never waits for the gpu.
+///
+/// Only one scope can be open at a time, as GL_TIME_ELAPSED queries can't nest.
+pub struct Profiler {
+    pub show_graph: bool,
+
+    created: Instant,
+    frame: u64,
+    frame_start: Instant,
+    current: Option<PendingFrame>,
+    open_scope: Option<(&'static str, Instant, Option<usize>)>,
+    pending: VecDeque<PendingFrame>,
+    history: VecDeque<FrameTiming>,
+    query_pool: QueryPool,
+    graph_texture: Option<Texture2D>,  // None until the graph is first shown
+}
+
+impl Profiler {
+    pub fn new() -> Profiler {
+        Profiler { show_graph: false, created: Instant::now(), frame: 0, frame_start: Instant::now(),
+            current: None, open_scope: None, pending: VecDeque::new(),
+            history: VecDeque::with_capacity(HISTORY_FRAMES), query_pool: QueryPool::default(),
+            graph_texture: None }
+    }
+
+    pub fn begin_frame(&mut self) {
+        self.frame_start = Instant::now();
+        self.current = Some(PendingFrame {
+            timing: FrameTiming { frame: self.frame, start: self.frame_start - self.created,
+                cpu_time: Duration::ZERO, scopes: Vec::new() },
+            queries: Vec::new(),
+        });
+    }
+
+    /// Starts timing a scope, with a gpu query if it submits gpu work.
+    pub fn begin(&mut self, name: &'static str, gpu: bool) {
+        self.end();
+        let query = gpu.then(|| {
+            let index = self.query_pool.take();
+            self.query_pool.queries[index].begin();
+            index
+        });
+        self.open_scope = Some((name, Instant::now(), query));
+    }
+
+    /// Stops timing the open scope, if there is one.
+    pub fn end(&mut self) {
+        let Some((name, start, query)) = self.open_scope.take() else { return };
+        if let Some(index) = query {
+            self.query_pool.queries[index].end();
+        }
+        if let Some(current) = &mut self.current {
+            current.timing.scopes.push(ScopeTiming { name, cpu_start: start - self.created,
+                cpu_time: start.elapsed(), gpu_time: None });
+            current.queries.push(query);
+        }
+    }
+
+    pub fn end_frame(&mut self) {
+        self.end();
+        if let Some(mut current) = self.current.take() {
+            current.timing.cpu_time = self.frame_start.elapsed();
+            self.pending.push_back(current);
+        }
+        self.frame += 1;
+
+        while !self.pending.is_empty() {
+            let waited_long_enough = self.pending.len() > QUERY_FRAMES;
+            let frame = self.pending.front_mut().unwrap();
+            let mut finished = true;
+            for (scope, query) in frame.timing.scopes.iter_mut().zip(&frame.queries) {
+                if let (Some(index), None) = (query, scope.gpu_time) {
+                    scope.gpu_time = self.query_pool.result(*index);
+                    finished &= scope.gpu_time.is_some();
+                }
+            }
+            if !finished && !waited_long_enough {
+                break;
+            }
+
+            let frame = self.pending.pop_front().unwrap();
+            self.query_pool.free.extend(frame.queries.iter().flatten());
+            if self.history.len() == HISTORY_FRAMES {
+                self.history.pop_front();
+            }
+            self.history.push_back(frame.timing);
+        }
+    }
+
+    /// Finished frames, oldest first.
+    pub fn history(&self) -> &VecDeque<FrameTiming> {
+        &self.history
+    }
+
+    /// Draws the recent frames as stacked bars, one colour per scope, gpu time where known.
+    pub fn graph(&self) -> RgbaImage {
+        let mut image = RgbaImage::from_pixel(GRAPH_WIDTH, GRAPH_HEIGHT, GRAPH_BACKGROUND);
+        let pixels_per_second = GRAPH_HEIGHT as f32 / GRAPH_MAX_TIME;
+        let offset = GRAPH_WIDTH as usize - self.history.len();
+
+        for (column, frame) in self.history.iter().enumerate() {
+            let mut bottom = 0.0;
+            for (index, scope) in frame.scopes.iter().enumerate() {
+                let time = scope.gpu_time.unwrap_or(scope.cpu_time).as_secs_f32();
+                let top = bottom + time * pixels_per_second;
+                for y in bottom as u32..(top as u32).min(GRAPH_HEIGHT) {
+                    image.put_pixel((column + offset) as u32, GRAPH_HEIGHT - 1 - y,
+                                    SCOPE_COLORS[index % SCOPE_COLORS.len()]);
+                }
+                bottom = top;
+            }
+        }
+
+        let target_line = GRAPH_HEIGHT - 1 - (pixels_per_second / 60.0) as u32;
+        for x in 0..GRAPH_WIDTH {
+            image.put_pixel(x, target_line, GRAPH_TARGET_LINE);
+        }
+        image
+    }
+
+    /// Updates the graph on the gpu, returning an RGBA8 texture GRAPH_WIDTH by GRAPH_HEIGHT.
+    pub fn graph_texture(&mut self) -> &Texture2D {
+        let graph = image::imageops::flip_vertical(&self.graph());  // GL's first row is the bottom
+        let texture = self.graph_texture
+            .get_or_insert_with(|| Texture2D::new(GRAPH_WIDTH, GRAPH_HEIGHT, gl::RGBA8));
+        texture.upload_rgba8(graph.as_raw());
+        texture
+    }
+
+    /// Writes the history to profiles/<timestamp>.csv and a Chrome trace in .json, which can be
+    /// opened in chrome://tracing or Perfetto. Returns the CSV's path.
+    pub fn export(&self) -> Result<PathBuf, String> {
+        fs::create_dir_all(PROFILE_DIR)
+            .map_err(|error| format!("Failed to create {}: {}", PROFILE_DIR, error))?;
+        let path = Path::new(PROFILE_DIR).join(timestamp());
+        write(&path.with_extension("csv"), &self.csv())?;
+        write(&path.with_extension("json"), &self.chrome_trace())?;
+        Ok(path.with_extension("csv"))
+    }
+
+    /// One row per scope per frame, times in milliseconds.
+    pub fn csv(&self) -> String {
+        let mut csv = String::from("frame,scope,cpu_start_ms,cpu_ms,gpu_ms\n");
+        for frame in &self.history {
+            for scope in &frame.scopes {
+                let gpu = scope.gpu_time.map_or(String::new(), |time| milliseconds(time).to_string());
+                writeln!(csv, "{},{},{},{},{}", frame.frame, scope.name,
+                         milliseconds(scope.cpu_start), milliseconds(scope.cpu_time), gpu).unwrap();
+            }
+        }
+        csv
+    }
+
+    /// Chrome's trace event format, with cpu scopes on one track and gpu scopes on another.
+    /// Timer queries only give durations, so gpu scopes are laid end to end from the start of
+    /// their frame.
+    pub fn chrome_trace(&self) -> String {
+        let mut events = Vec::new();
+        for frame in &self.history {
+            events.push(trace_event(&format!("frame {}", frame.frame), "cpu", 1, frame.start,
+                                    frame.cpu_time));
+            let mut gpu_start = frame.start;
+            for scope in &frame.scopes {
+                events.push(trace_event(scope.name, "cpu", 1, scope.cpu_start, scope.cpu_time));
+                if let Some(gpu_time) = scope.gpu_time {
+                    events.push(trace_event(scope.name, "gpu", 2, gpu_start, gpu_time));
+                    gpu_start += gpu_time;
+                }
+            }
+        }
+        format!("{{\"traceEvents\":[\n{}\n]}}\n", events.join(",\n"))
+    }
+}
+
+impl Default for Profiler {
+    fn default() -> Profiler {
+        Profiler::new()
+    }
+}
+
+fn trace_event(name: &str, category: &str, thread: u32, start: Duration, duration: Duration) -> String {
+    format!("{{\"name\":\"{}\",\"cat\":\"{}\",\"ph\":\"X\",\"pid\":1,\"tid\":{},\"ts\":{},\"dur\":{}}}",
+            name, category, thread, start.as_micros(), duration.as_micros())
+}
+
+fn milliseconds(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn write(path: &Path, contents: &str) -> Result<(), String> {
+    fs::write(path, contents).map_err(|error| format!("Failed to save {}: {}", path.display(), error))
+}
@@ -1,2 +1,3 @@
 pub mod texture_drawer;
+pub mod upscaler;
 mod vertex_buffers;
\ No newline at end of file
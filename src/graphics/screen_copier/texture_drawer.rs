@@ -1,41 +1,114 @@
-use std::ffi::CString;
 use std::ptr;
 
-use gl::types::GLuint;
-use sdl2::EventPump;
+use gl::types::{GLint, GLsizei};
+use sdl2::Sdl;
+use sdl2::video::FullscreenType;
 
-use crate::graphics::{sgl, update_texture_binding_point};
+use crate::graphics::{sgl, DebugGroup, Texture2D};
+use crate::graphics::backend::{GlBackend, Rect, RenderBackend};
+use crate::graphics::capabilities::GlCapabilities;
 use crate::graphics::screen_copier::vertex_buffers::VertexBuffers;
+use crate::graphics::shader_utils::error::ShaderError;
+use crate::graphics::shader_utils::hot_reload::shader_file;
+use crate::graphics::shader_utils::graphics_program;
 use crate::graphics::shader_utils::program::Program;
-use crate::graphics::shader_utils::shader::Shader;
 use crate::graphics::window::Window;
 
-pub struct TextureDrawer {
-    window: Window,
-    shader_program: Program,
-    vertex_buffers: VertexBuffers
+pub struct TextureDrawer<B: RenderBackend = GlBackend> {
+    backend: B,
+    surface: B::Surface,
 }
 
 impl TextureDrawer {
     /// Create a window and do setup for drawing texture to screen.
     pub fn new(name: &'static str, width: u32, height: u32) -> TextureDrawer {
-        let window = Window::new(name, width, height);
-        let shader_program = TextureDrawer::load_shaders();
-        let vertex_buffers = TextureDrawer::create_vertex_buffers();
-        TextureDrawer {window, shader_program, vertex_buffers}
+        TextureDrawer::from_window(Window::new(name, width, height))
+    }
+
+    pub(crate) fn from_window(window: Window) -> TextureDrawer {
+        let surface = GlSurface::new(window).unwrap_or_else(|error| panic!("{}", error));
+        TextureDrawer::with_backend(GlBackend, surface)
+    }
+
+    /// Recompiles the shaders, keeping the old ones if they fail to compile.
+    pub fn reload_shaders(&mut self) -> Result<(), ShaderError> {
+        self.surface.reload_shaders()
+    }
+
+    pub fn capabilities(&self) -> &GlCapabilities {
+        &self.surface.window.capabilities
+    }
+
+    pub fn sdl(&self) -> &Sdl {
+        return &self.surface.window.sdl;
+    }
+
+    pub fn toggle_fullscreen(&mut self, fullscreen: FullscreenType) {
+        self.surface.window.toggle_fullscreen(fullscreen);
+    }
+
+    pub fn save_window_geometry(&self) {
+        self.surface.window.save_geometry();
+    }
+}
+
+impl<B: RenderBackend> TextureDrawer<B> {
+    pub fn with_backend(backend: B, surface: B::Surface) -> TextureDrawer<B> {
+        TextureDrawer {backend, surface}
+    }
+
+    /// Draws texture over the whole window. Call present to show it.
+    pub fn draw(&mut self, texture: &B::Texture) {
+        self.backend.clear(&mut self.surface);
+        let size = self.backend.surface_size(&self.surface);
+        self.backend.draw_texture(&mut self.surface, texture, Rect::covering(size), false);
+    }
+
+    /// Alpha blends texture over part of the window, position is in pixels from the bottom left.
+    pub fn draw_overlay(&mut self, texture: &B::Texture, x: u32, y: u32, width: u32, height: u32) {
+        self.backend.draw_texture(&mut self.surface, texture, Rect::new(x, y, width, height), true);
+    }
+
+    /// Shows what was drawn this frame.
+    pub fn present(&mut self) {
+        self.backend.present(&mut self.surface);
     }
 
-    fn load_shaders() -> Program {
-        let vert_shader =
-            Shader::from_vert_source(&CString::new(include_str!("texture_drawer.vert")).unwrap())
-                .unwrap();
-        let frag_shader =
-            Shader::from_frag_source(&CString::new(include_str!("texture_drawer.frag")).unwrap())
-                .unwrap();
+    pub fn surface(&self) -> &B::Surface {
+        &self.surface
+    }
+
+    pub fn get_size(&mut self) -> (u32, u32) {
+        return self.backend.surface_size(&self.surface);
+    }
+
+    /// Call after the window is resized, updates the viewport and returns the new size.
+    pub fn update_size(&mut self) -> (u32, u32) {
+        return self.backend.resize_surface(&mut self.surface);
+    }
+}
+
+/// A window and the shaders to draw textures to it, for GlBackend.
+pub struct GlSurface {
+    pub(crate) window: Window,
+    shader_program: Program,
+    vertex_buffers: VertexBuffers
+}
 
-        let shader_program = Program::from_shaders(
-            &[vert_shader, frag_shader]).unwrap();
-        return shader_program;
+impl GlSurface {
+    pub(crate) fn new(window: Window) -> Result<GlSurface, ShaderError> {
+        let shader_program = GlSurface::load_shaders()?;
+        let vertex_buffers = GlSurface::create_vertex_buffers();
+        Ok(GlSurface {window, shader_program, vertex_buffers})
+    }
+
+    fn load_shaders() -> Result<Program, ShaderError> {
+        graphics_program(shader_file!("texture_drawer.vert"), shader_file!("texture_drawer.frag"), &[])
+    }
+
+    fn reload_shaders(&mut self) -> Result<(), ShaderError> {
+        self.shader_program = GlSurface::load_shaders()?;
+        Ok(())
     }
 
     fn create_vertex_buffers() -> VertexBuffers {
@@ -56,16 +129,18 @@ impl TextureDrawer {
         return VertexBuffers::new(vertices, indices, layout_sizes);
     }
 
-
-    pub fn draw(&mut self, texture: GLuint) {
+    pub(crate) fn draw_quad(&mut self, texture: &Texture2D, area: Rect, blend: bool) {
+        let _group = DebugGroup::push("Drawing to the window");
+        sgl::Viewport(area.x as GLint, area.y as GLint, area.width as GLsizei, area.height as GLsizei);
+        if blend {
+            sgl::Enable(gl::BLEND);
+            sgl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+        }
         self.shader_program.set_used();
 
-        update_texture_binding_point(texture, 0);
-
-        sgl::Clear(gl::COLOR_BUFFER_BIT);
+        let _texture = texture.bind(0);
 
-        sgl::BindVertexArray(self.vertex_buffers.vao);
-        sgl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.vertex_buffers.ebo);
+        let _vertex_array = self.vertex_buffers.vao.bind();  // Has the index buffer too
         sgl::DrawElements(
             gl::TRIANGLES,
             6,
@@ -73,14 +148,9 @@ impl TextureDrawer {
             ptr::null()
         );
 
-        self.window.window.gl_swap_window();
+        if blend {
+            sgl::Disable(gl::BLEND);
+        }
+        sgl::Viewport(0, 0, self.window.width as GLsizei, self.window.height as GLsizei);
     }
-
-    pub fn get_event_pump(&mut self) -> EventPump {
-        return self.window.sdl.event_pump().unwrap();
-    }
-
-    pub fn get_size(&mut self) -> (u32, u32) {
-        return (self.window.width, self.window.height);
-    }
-}
\ No newline at end of file
+}
@@ -0,0 +1,149 @@
+use crate::graphics::{sgl, DebugGroup, Texture2D};
+use crate::graphics::shader_utils::{compute_program, require_interface};
+use crate::graphics::shader_utils::error::ShaderError;
+use crate::graphics::shader_utils::hot_reload::shader_file;
+use crate::graphics::shader_utils::program::Program;
+
+const WORKGROUP_SIZE: u32 = 16;
+const JITTER_SEQUENCE_LENGTH: u32 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UpscaleFilter {
+    Bilinear,
+    EdgeAdaptive,  // FSR1 style
+    Temporal,  // Needs the ray tracer to jitter its samples, see Upscaler::jitter
+}
+
+impl UpscaleFilter {
+    /// The next filter, for cycling through them at runtime.
+    pub fn next(self) -> UpscaleFilter {
+        match self {
+            UpscaleFilter::Bilinear => UpscaleFilter::EdgeAdaptive,
+            UpscaleFilter::EdgeAdaptive => UpscaleFilter::Temporal,
+            UpscaleFilter::Temporal => UpscaleFilter::Bilinear,
+        }
+    }
+
+    fn shader_id(self) -> i32 {  // Matches the FILTER_ defines in upscaler.comp
+        match self {
+            UpscaleFilter::Bilinear => 0,
+            UpscaleFilter::EdgeAdaptive => 1,
+            UpscaleFilter::Temporal => 2,
+        }
+    }
+}
+
+/// Lets the ray tracer render at a fraction of the output resolution, then scales its image back
+/// up to the output resolution.
+pub struct Upscaler {
+    pub render_scale: f32,  // Fraction of the output resolution to trace at, 1 disables upscaling
+    pub filter: UpscaleFilter,
+    pub history_blend: f32,  // How much of a new sample goes into the temporal history
+
+    shader_program: Program,
+    size: (u32, u32),
+    textures: Vec<Texture2D>,  // Output and history, swapped every frame, empty until first used
+    frame: u32,
+    history_valid: bool,
+}
+
+impl Upscaler {
+    pub fn new(render_scale: f32, filter: UpscaleFilter) -> Upscaler {
+        let shader_program = Upscaler::load_shaders().unwrap_or_else(|error| panic!("{}", error));
+
+        Upscaler { render_scale, filter, history_blend: 0.1, shader_program, size: (0, 0),
+            textures: Vec::new(), frame: 0, history_valid: false }
+    }
+
+    fn load_shaders() -> Result<Program, ShaderError> {
+        let file = shader_file!("upscaler.comp");
+        require_interface(compute_program(file, &[])?, file, &[
+            ("filter_mode", gl::INT), ("jitter", gl::FLOAT_VEC2), ("reset_history", gl::BOOL),
+            ("history_blend", gl::FLOAT),
+        ], &[])
+    }
+
+    /// Recompiles the shader, keeping the old one if it fails to compile.
+    pub fn reload_shaders(&mut self) -> Result<(), ShaderError> {
+        self.shader_program = Upscaler::load_shaders()?;
+        Ok(())
+    }
+
+    /// The resolution the ray tracer should render at for a given output resolution.
+    pub fn render_size(&self, output_width: u32, output_height: u32) -> (u32, u32) {
+        let scale = self.render_scale.clamp(0.1, 1.0);
+        (((output_width as f32 * scale).round() as u32).max(1),
+         ((output_height as f32 * scale).round() as u32).max(1))
+    }
+
+    /// Sub pixel offset the ray tracer should use this frame, in render pixels. Only temporal
+    /// upscaling makes use of jitter, so it's zero for the other filters.
+    pub fn jitter(&self) -> (f32, f32) {
+        if self.filter != UpscaleFilter::Temporal {
+            return (0.0, 0.0);
+        }
+        let index = self.frame % JITTER_SEQUENCE_LENGTH + 1;
+        (halton(index, 2) - 0.5, halton(index, 3) - 0.5)
+    }
+
+    /// Throw away the temporal history, e.g. because the camera moved.
+    pub fn reset_history(&mut self) {
+        self.history_valid = false;
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        if self.size == (width, height) {
+            return;
+        }
+        self.textures = (0..2).map(|index| {
+            let texture = Texture2D::new(width, height, gl::RGBA32F);
+            texture.set_label(&format!("Upscaled {}", index));
+            texture
+        }).collect();
+        self.size = (width, height);
+        self.history_valid = false;
+    }
+
+    /// Scales the ray traced texture up to the output resolution. Returns the texture that should
+    /// be used from now on, which is the input texture itself if no upscaling was needed.
+    pub fn upscale<'a>(&'a mut self, texture: &'a Texture2D, output_size: (u32, u32)) -> &'a Texture2D {
+        if texture.size() == output_size && self.filter != UpscaleFilter::Temporal {
+            self.history_valid = false;
+            return texture;
+        }
+        self.resize(output_size.0, output_size.1);
+
+        let history = &self.textures[(self.frame % 2) as usize];
+        let output = &self.textures[((self.frame + 1) % 2) as usize];
+        let jitter = self.jitter();
+
+        let _group = DebugGroup::push("Upscaling");
+        self.shader_program.set_used();
+        self.shader_program.set_or_log("filter_mode", self.filter.shader_id());
+        self.shader_program.set_or_log("jitter", jitter);
+        self.shader_program.set_or_log("reset_history", !self.history_valid);
+        self.shader_program.set_or_log("history_blend", self.history_blend);
+        texture.bind_image(0, gl::READ_ONLY);
+        history.bind_image(1, gl::READ_ONLY);
+        output.bind_image(2, gl::WRITE_ONLY);
+        sgl::DispatchCompute(output_size.0.div_ceil(WORKGROUP_SIZE),
+                             output_size.1.div_ceil(WORKGROUP_SIZE), 1);
+        sgl::MemoryBarrier(gl::SHADER_IMAGE_ACCESS_BARRIER_BIT | gl::TEXTURE_FETCH_BARRIER_BIT);
+
+        self.frame = self.frame.wrapping_add(1);
+        self.history_valid = self.filter == UpscaleFilter::Temporal;
+        return output;
+    }
+}
+
+/// Element of the halton low discrepancy sequence, used for evenly spread jitter.
+pub(crate) fn halton(mut index: u32, base: u32) -> f32 {
+    let mut fraction = 1.0;
+    let mut result = 0.0;
+    while index > 0 {
+        fraction /= base as f32;
+        result += fraction * (index % base) as f32;
+        index /= base;
+    }
+    result
+}
@@ -1,40 +1,31 @@
 use std::ffi::c_void;
 
-use gl::types::{GLsizeiptr, GLuint};
+use gl::types::GLuint;
 
-use crate::graphics::sgl;
+use crate::graphics::{sgl, Buffer, VertexArray};
 
 /// Creates vertex information to upload to the gpu for us.
 pub struct VertexBuffers {
-    pub vbo: GLuint,  // The thing that puts it on the gpu?
-    pub vao: GLuint,  // Vertexes
-    pub ebo: GLuint,  // Indexes
+    pub vbo: Buffer<f32>,  // The thing that puts it on the gpu?
+    pub vao: VertexArray,  // Vertexes
+    pub ebo: Buffer<u32>,  // Indexes
 }
 
 impl VertexBuffers {
     pub fn new(vertices: Vec<f32>, indices: Vec<u32>, layout_sizes: Vec<i32>) -> VertexBuffers {
 
         // The thing that puts it on the gpu?
-        let mut vbo: GLuint = 0;
-        sgl::GenBuffers(1, &mut vbo);
-        sgl::BindBuffer(gl::ARRAY_BUFFER, vbo);
-        sgl::BufferData(
-            gl::ARRAY_BUFFER,
-            (vertices.len() * std::mem::size_of::<f32>()) as GLsizeiptr,  // size of data in bytes
-            vertices.as_ptr() as *const gl::types::GLvoid,  // pointer to data
-            gl::STATIC_DRAW,  // usage
-        );
+        let vbo = Buffer::from_slice(gl::ARRAY_BUFFER, &vertices, gl::STATIC_DRAW);
 
 
         // set up vertex array object
-        let mut vao: GLuint = 0;
-        sgl::GenVertexArrays(1, &mut vao);
-        sgl::BindVertexArray(vao);
+        let vao = VertexArray::new();
+        let vao_bound = vao.bind();
 
         let vertex_size: i32 = layout_sizes.iter().sum();
         let mut offset: i32 = 0;
         for (n, layout_size) in layout_sizes.iter().enumerate() {
-            sgl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            let _vbo_bound = vbo.bind();
             sgl::EnableVertexAttribArray(n as GLuint);
             sgl::VertexAttribPointer(
                 n as GLuint,
@@ -46,18 +37,13 @@ impl VertexBuffers {
             );
             offset += layout_size;
         }
-        sgl::BindBuffer(gl::ARRAY_BUFFER, vbo);
-        sgl::BindVertexArray(vao);
 
 
-        // set up indices array object
-        let mut ebo: GLuint = 0;
-        sgl::GenBuffers(1, &mut ebo);
-        sgl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
-        sgl::BufferData(gl::ELEMENT_ARRAY_BUFFER,
-                        (indices.len() * std::mem::size_of::<u32>()) as GLsizeiptr,
-                        indices.as_ptr() as *const c_void,
-                        gl::STATIC_DRAW);
+        // set up indices array object, which the vertex array remembers if it's bound while the
+        // vertex array is, so it's left bound rather than through a guard
+        let ebo = Buffer::from_slice(gl::ELEMENT_ARRAY_BUFFER, &indices, gl::STATIC_DRAW);
+        sgl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo.id());
+        drop(vao_bound);
 
 
         return VertexBuffers {vbo, vao, ebo};
@@ -0,0 +1,80 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::graphics::capabilities::GlCapabilities;
+use crate::graphics::headless::{read_ldr_texture, render_image};
+use crate::graphics::post_processing::post_processor::PostProcessor;
+use crate::graphics::Texture2D;
+use crate::graphics::world_renderer::ray_tracer::RayTracer;
+
+const SCREENSHOT_DIR: &str = "screenshots";
+const STILL_TILE_SIZE: (u32, u32) = (512, 512);  // Big stills are split up so no dispatch runs too long
+
+/// Saves a post processed texture as it is on screen, returning where it went.
+pub fn save_screenshot(texture: &Texture2D) -> Result<PathBuf, String> {
+    let path = new_screenshot_path("")?.with_extension("png");
+    read_ldr_texture(texture).save(&path)
+        .map_err(|error| format!("Failed to save {}: {}", path.display(), error))?;
+    Ok(path)
+}
+
+/// Re-renders the current view at scale times the window resolution with many samples, then
+/// saves it both post processed as a PNG and as linear HDR in an EXR. Returns the PNG's path.
+/// Fails without rendering if the still would be bigger than the driver's largest texture.
+pub fn save_high_res_still(ray_tracer: &mut RayTracer, post_processor: &mut PostProcessor,
+                           capabilities: &GlCapabilities, window_size: (u32, u32), scale: u32,
+                           samples: u32) -> Result<PathBuf, String> {
+    let scale = scale.max(1);
+    let (width, height) = (window_size.0.saturating_mul(scale), window_size.1.saturating_mul(scale));
+    let max_size = capabilities.limit("MAX_TEXTURE_SIZE").map_or(u32::MAX, |size| size as u32);
+    if width > max_size || height > max_size {
+        return Err(format!("A {}x{} still is bigger than the largest texture the driver supports, {}x{}. \
+                            Use a lower --still-scale", width, height, max_size, max_size));
+    }
+    let previous_tile_size = ray_tracer.settings.tile_size;
+    ray_tracer.settings.tile_size.get_or_insert(STILL_TILE_SIZE);
+    let image = render_image(ray_tracer, post_processor, width, height, samples);
+    ray_tracer.settings.tile_size = previous_tile_size;
+
+    let path = new_screenshot_path("_still")?;
+    image.save(&path.with_extension("png"))?;
+    image.save(&path.with_extension("exr"))?;
+    Ok(path.with_extension("png"))
+}
+
+/// screenshots/<timestamp><suffix> without an extension, numbered if one was already taken this
+/// second.
+fn new_screenshot_path(suffix: &str) -> Result<PathBuf, String> {
+    fs::create_dir_all(SCREENSHOT_DIR)
+        .map_err(|error| format!("Failed to create {}: {}", SCREENSHOT_DIR, error))?;
+
+    let name = format!("{}{}", timestamp(), suffix);
+    let mut path = Path::new(SCREENSHOT_DIR).join(&name);
+    let mut number = 1;
+    while path.with_extension("png").exists() {
+        number += 1;
+        path = Path::new(SCREENSHOT_DIR).join(format!("{}_{}", name, number));
+    }
+    Ok(path)
+}
+
+/// The current UTC time as YYYY-MM-DD_HH-MM-SS, which sorts in order and is safe in file names.
+pub fn timestamp() -> String {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs());
+    let (days, seconds_of_day) = (seconds / 86400, seconds % 86400);
+
+    // Days since 1970 to a civil date, from Howard Hinnant's days_from_civil inverse
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}_{:02}-{:02}-{:02}", year, month, day, seconds_of_day / 3600,
+            seconds_of_day / 60 % 60, seconds_of_day % 60)
+}
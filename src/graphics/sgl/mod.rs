@@ -0,0 +1,634 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+
+use std::ffi::{c_char, c_void, CStr};
+use std::ptr;
+
+use gl::types::{GLbitfield, GLboolean, GLchar, GLDEBUGPROC, GLenum, GLfloat, GLint, GLintptr, GLsizei,
+                GLsizeiptr, GLsync, GLuint, GLuint64};
+
+use crate::graphics::shader_utils::shader::Shader;
+
+pub use objects::{BindGuard, Buffer, DebugGroup, Framebuffer, Query, Texture2D, TextureArray, VertexArray};
+
+mod objects;
+
+pub fn CreateProgram() -> GLuint {
+    unsafe {
+        return gl::CreateProgram();
+    }
+}
+
+pub fn AttachShader(program_id: GLuint, shader: &Shader) {
+    unsafe {
+        gl::AttachShader(program_id, shader.id());
+    }
+}
+
+pub fn LinkProgram(program_id: GLuint) {
+    unsafe {
+        gl::LinkProgram(program_id);
+    }
+}
+
+pub fn GetProgramiv(program_id: GLuint, pname: GLenum, params: *mut GLint) {
+    unsafe {
+        gl::GetProgramiv(program_id, pname, params);
+    }
+}
+
+
+pub fn GetProgramInfoLog(program_id: GLuint, len: GLint, length: *mut GLsizei, error: *mut GLchar) {
+    unsafe {
+        gl::GetProgramInfoLog(
+            program_id,
+            len,
+            length,
+            error
+        );
+    }
+}
+
+pub fn DetachShader(program_id: GLuint, id: GLuint) {
+    unsafe {
+        gl::DetachShader(program_id, id);
+    }
+}
+
+pub fn DeleteShader(shader_id: GLuint) {
+    unsafe {
+        gl::DeleteShader(shader_id);
+    }
+}
+
+pub fn UseProgram(id: GLuint) {
+    unsafe {
+        gl::UseProgram( id);
+    }
+}
+
+pub fn DeleteProgram(id: GLuint) {
+    unsafe {
+        gl::DeleteProgram(id);
+    }
+}
+
+pub fn ProgramParameteri(program_id: GLuint, pname: GLenum, value: GLint) {
+    unsafe {
+        gl::ProgramParameteri(program_id, pname, value);
+    }
+}
+
+/// The program's binary and its format, empty if the driver can't give one.
+pub fn GetProgramBinary(program_id: GLuint) -> (GLenum, Vec<u8>) {
+    let mut length: GLint = 0;
+    GetProgramiv(program_id, gl::PROGRAM_BINARY_LENGTH, &mut length);
+    let mut binary = vec![0u8; length.max(0) as usize];
+    let mut written: GLsizei = 0;
+    let mut format: GLenum = 0;
+    unsafe {
+        gl::GetProgramBinary(program_id, length, &mut written, &mut format, binary.as_mut_ptr() as *mut c_void);
+    }
+    binary.truncate(written.max(0) as usize);
+    (format, binary)
+}
+
+pub fn ProgramBinary(program_id: GLuint, format: GLenum, binary: &[u8]) {
+    unsafe {
+        gl::ProgramBinary(program_id, format, binary.as_ptr() as *const c_void, binary.len() as GLsizei);
+    }
+}
+
+/// A GL string like gl::VENDOR, empty if there isn't a context.
+pub fn GetString(name: GLenum) -> String {
+    unsafe {
+        let string = gl::GetString(name);
+        if string.is_null() {
+            return String::new();
+        }
+        return CStr::from_ptr(string as *const c_char).to_string_lossy().into_owned();
+    }
+}
+
+/// One of an indexed list of strings, e.g. gl::EXTENSIONS.
+pub fn GetStringi(name: GLenum, index: GLuint) -> String {
+    unsafe {
+        let string = gl::GetStringi(name, index);
+        if string.is_null() {
+            return String::new();
+        }
+        return CStr::from_ptr(string as *const c_char).to_string_lossy().into_owned();
+    }
+}
+
+pub fn ShaderSource(shader: GLuint, count: i32, string: &*const c_char, length: *const GLint) {
+    unsafe {
+        gl::ShaderSource(shader, count, string, length);
+    }
+}
+
+pub fn CompileShader(id: GLuint) {
+    unsafe {
+        gl::CompileShader(id);
+    }
+}
+
+pub fn GetShaderiv(id: GLuint, pname: GLenum, params: *mut GLint) {
+    unsafe {
+        gl::GetShaderiv(id, pname, params);
+    }
+}
+
+pub fn GetShaderInfoLog(shader: GLuint, buf_size: GLsizei, length: *mut GLsizei,
+                        info_log: *mut GLchar) {
+    unsafe {
+        gl::GetShaderInfoLog(shader, buf_size, length, info_log);
+    }
+}
+
+pub fn GenVertexArrays(n: GLint, arrays: *mut GLuint) {
+    unsafe {
+        gl::GenVertexArrays(n, arrays);
+    }
+}
+
+pub fn GenBuffers(size: GLsizei, buffers: *mut GLuint) {
+    unsafe {
+        gl::GenBuffers(size, buffers);
+    }
+}
+
+pub fn BindVertexArray(array: GLuint) {
+    unsafe {
+        gl::BindVertexArray(array);
+    }
+}
+
+pub fn BindBuffer(target: GLenum, buffer: GLuint) {
+    unsafe {
+        gl::BindBuffer(target, buffer);
+    }
+}
+
+pub fn BufferData(target: GLenum, size: GLsizeiptr, data: *const c_void, usage: GLenum) {
+    unsafe {
+        gl::BufferData(target, size, data, usage);
+    }
+}
+
+pub fn VertexAttribPointer(index: GLuint, size: GLint, type_: GLenum, normalized: GLboolean,
+                           stride: GLsizei, pointer: *const c_void) {
+    unsafe {
+        gl::VertexAttribPointer(index, size, type_, normalized, stride, pointer);
+    }
+}
+
+pub fn EnableVertexAttribArray(index: GLuint) {
+    unsafe {
+        gl::EnableVertexAttribArray(index);
+    }
+}
+
+pub fn DrawElements(mode: GLenum, count: GLsizei, type_: GLenum, indices: *const c_void) {
+    unsafe {
+        gl::DrawElements(mode, count, type_, indices);
+    }
+}
+
+pub fn Viewport(x: GLint, y: GLint, width: GLsizei, height: GLsizei) {
+    unsafe {
+        gl::Viewport(x, y, width, height);
+    }
+}
+
+pub fn Clear(mask: GLbitfield) {
+    unsafe {
+        gl::Clear(mask);
+    }
+}
+
+pub fn CreateShader(type_: GLenum) -> GLuint {
+    unsafe {
+        return gl::CreateShader(type_);
+    }
+}
+
+pub fn Uniform1i(location: GLint, value: GLint) {
+    unsafe {
+        gl::Uniform1i(location, value);
+    }
+}
+
+pub fn Uniform1f(location: GLint, value: GLfloat) {
+    unsafe {
+        gl::Uniform1f(location, value);
+    }
+}
+
+pub fn Uniform2f(location: GLint, v0: GLfloat, v1: GLfloat) {
+    unsafe {
+        gl::Uniform2f(location, v0, v1);
+    }
+}
+
+pub fn Uniform2i(location: GLint, v0: GLint, v1: GLint) {
+    unsafe {
+        gl::Uniform2i(location, v0, v1);
+    }
+}
+
+pub fn GetUniformLocation(program_id: GLuint, name: &CStr) -> GLint {
+    unsafe {
+        return gl::GetUniformLocation(program_id, name.as_ptr());
+    }
+}
+
+pub fn GetUniformiv(program_id: GLuint, location: GLint, params: *mut GLint) {
+    unsafe {
+        gl::GetUniformiv(program_id, location, params);
+    }
+}
+
+pub fn GetProgramInterfaceiv(program_id: GLuint, interface: GLenum, pname: GLenum, params: *mut GLint) {
+    unsafe {
+        gl::GetProgramInterfaceiv(program_id, interface, pname, params);
+    }
+}
+
+pub fn GetProgramResourceiv(program_id: GLuint, interface: GLenum, index: GLuint, props: &[GLenum],
+                            params: &mut [GLint]) {
+    unsafe {
+        gl::GetProgramResourceiv(program_id, interface, index, props.len() as GLsizei, props.as_ptr(),
+                                 params.len() as GLsizei, ptr::null_mut(), params.as_mut_ptr());
+    }
+}
+
+pub fn GetProgramResourceName(program_id: GLuint, interface: GLenum, index: GLuint, buffer: &mut [u8])
+                              -> GLsizei {
+    let mut length: GLsizei = 0;
+    unsafe {
+        gl::GetProgramResourceName(program_id, interface, index, buffer.len() as GLsizei, &mut length,
+                                   buffer.as_mut_ptr() as *mut GLchar);
+    }
+    length
+}
+
+pub fn ProgramUniform1i(program_id: GLuint, location: GLint, v0: GLint) {
+    unsafe {
+        gl::ProgramUniform1i(program_id, location, v0);
+    }
+}
+
+pub fn ProgramUniform2i(program_id: GLuint, location: GLint, v0: GLint, v1: GLint) {
+    unsafe {
+        gl::ProgramUniform2i(program_id, location, v0, v1);
+    }
+}
+
+pub fn ProgramUniform1ui(program_id: GLuint, location: GLint, v0: GLuint) {
+    unsafe {
+        gl::ProgramUniform1ui(program_id, location, v0);
+    }
+}
+
+pub fn ProgramUniform1f(program_id: GLuint, location: GLint, v0: GLfloat) {
+    unsafe {
+        gl::ProgramUniform1f(program_id, location, v0);
+    }
+}
+
+pub fn ProgramUniform2f(program_id: GLuint, location: GLint, v0: GLfloat, v1: GLfloat) {
+    unsafe {
+        gl::ProgramUniform2f(program_id, location, v0, v1);
+    }
+}
+
+pub fn ProgramUniform3f(program_id: GLuint, location: GLint, v0: GLfloat, v1: GLfloat, v2: GLfloat) {
+    unsafe {
+        gl::ProgramUniform3f(program_id, location, v0, v1, v2);
+    }
+}
+
+pub fn ProgramUniform4f(program_id: GLuint, location: GLint, v0: GLfloat, v1: GLfloat, v2: GLfloat,
+                        v3: GLfloat) {
+    unsafe {
+        gl::ProgramUniform4f(program_id, location, v0, v1, v2, v3);
+    }
+}
+
+pub fn ProgramUniformMatrix4fv(program_id: GLuint, location: GLint, value: &[[f32; 4]; 4]) {
+    unsafe {
+        gl::ProgramUniformMatrix4fv(program_id, location, 1, gl::FALSE, value.as_ptr() as *const GLfloat);
+    }
+}
+
+pub fn GenTextures(types: GLsizei, textures: &mut GLuint) {
+    unsafe {
+        gl::GenTextures(types, textures);
+    };
+}
+
+pub fn BindTexture(target: GLenum, texture: GLuint) {
+    unsafe {
+        gl::BindTexture(target, texture);
+    };
+}
+
+pub fn TexParameteri(target: GLenum, pname: GLenum, param: GLint) {
+    unsafe {
+        gl::TexParameteri(target, pname, param);
+    };
+}
+
+pub fn TexImage2D(target: GLenum, level: GLint, internal_format: GLint, width: GLsizei,
+                  height: GLsizei, border: GLint, format: GLenum, type_: GLenum,
+                  pixels: *const c_void) {
+    unsafe {
+        gl::TexImage2D(target, level, internal_format, width, height, border, format,
+                       type_, pixels);
+    };
+}
+
+pub fn ActiveTexture(texture: GLenum) {
+    unsafe {
+        gl::ActiveTexture(texture);
+    };
+}
+
+pub fn BindImageTexture(unit: GLuint, texture: GLuint, level: GLint, layered: GLboolean,
+                        layer: GLint, access: GLenum, format: GLenum) {
+    unsafe {
+        gl::BindImageTexture(unit, texture, level, layered, layer, access, format);
+    };
+}
+
+pub fn DispatchCompute(num_groups_x: GLuint, num_groups_y: GLuint, num_groups_z: GLuint) {
+    unsafe {
+        gl::DispatchCompute(num_groups_x, num_groups_y, num_groups_z);
+    };
+}
+
+pub fn MemoryBarrier(barriers: GLbitfield) {
+    unsafe {
+        gl::MemoryBarrier(barriers);
+    };
+}
+
+pub fn BindBufferBase(target: GLenum, index: GLuint, buffer: GLuint) {
+    unsafe {
+        gl::BindBufferBase(target, index, buffer);
+    };
+}
+
+pub fn BufferSubData(target: GLenum, offset: GLintptr, size: GLsizeiptr, data: *const c_void) {
+    unsafe {
+        gl::BufferSubData(target, offset, size, data);
+    };
+}
+
+pub fn GetBufferSubData(target: GLenum, offset: GLintptr, size: GLsizeiptr, data: *mut c_void) {
+    unsafe {
+        gl::GetBufferSubData(target, offset, size, data);
+    };
+}
+
+pub fn Enable(types: GLenum) {
+    unsafe {
+        gl::Enable(types);
+    };
+}
+
+pub fn DebugMessageCallback(callback: GLDEBUGPROC, userparam: *const c_void) {
+    unsafe {
+        gl::DebugMessageCallback(callback, userparam);
+    };
+}
+
+/// Turns debug messages matching a source, type and severity on or off, any of which can be
+/// gl::DONT_CARE.
+pub fn DebugMessageControl(source: GLenum, type_: GLenum, severity: GLenum, enabled: bool) {
+    unsafe {
+        gl::DebugMessageControl(source, type_, severity, 0, ptr::null(), enabled as GLboolean);
+    };
+}
+
+/// Names an object in debug messages and graphics debuggers. identifier is the kind of object,
+/// e.g. gl::TEXTURE.
+pub fn ObjectLabel(identifier: GLenum, name: GLuint, label: &str) {
+    unsafe {
+        gl::ObjectLabel(identifier, name, label.len() as GLsizei, label.as_ptr() as *const GLchar);
+    };
+}
+
+pub fn PushDebugGroup(message: &str) {
+    unsafe {
+        gl::PushDebugGroup(gl::DEBUG_SOURCE_APPLICATION, 0, message.len() as GLsizei,
+                           message.as_ptr() as *const GLchar);
+    };
+}
+
+pub fn PopDebugGroup() {
+    unsafe {
+        gl::PopDebugGroup();
+    };
+}
+
+pub fn Flush() {
+    unsafe {
+        gl::Flush();
+    };
+}
+
+pub fn Finish() {
+    unsafe {
+        gl::Finish();
+    };
+}
+
+pub fn DeleteTextures(n: GLsizei, textures: *const GLuint) {
+    unsafe {
+        gl::DeleteTextures(n, textures);
+    };
+}
+
+pub fn GetTexImage(target: GLenum, level: GLint, format: GLenum, type_: GLenum,
+                   pixels: *mut c_void) {
+    unsafe {
+        gl::GetTexImage(target, level, format, type_, pixels);
+    };
+}
+
+pub fn TexSubImage2D(target: GLenum, level: GLint, x_offset: GLint, y_offset: GLint,
+                     width: GLsizei, height: GLsizei, format: GLenum, type_: GLenum,
+                     pixels: *const c_void) {
+    unsafe {
+        gl::TexSubImage2D(target, level, x_offset, y_offset, width, height, format, type_, pixels);
+    };
+}
+
+pub fn Disable(types: GLenum) {
+    unsafe {
+        gl::Disable(types);
+    };
+}
+
+pub fn BlendFunc(source_factor: GLenum, destination_factor: GLenum) {
+    unsafe {
+        gl::BlendFunc(source_factor, destination_factor);
+    };
+}
+
+pub fn GenQueries(n: GLsizei, ids: *mut GLuint) {
+    unsafe {
+        gl::GenQueries(n, ids);
+    };
+}
+
+pub fn DeleteQueries(n: GLsizei, ids: *const GLuint) {
+    unsafe {
+        gl::DeleteQueries(n, ids);
+    };
+}
+
+pub fn BeginQuery(target: GLenum, id: GLuint) {
+    unsafe {
+        gl::BeginQuery(target, id);
+    };
+}
+
+pub fn EndQuery(target: GLenum) {
+    unsafe {
+        gl::EndQuery(target);
+    };
+}
+
+pub fn GetQueryObjectiv(id: GLuint, pname: GLenum, params: *mut GLint) {
+    unsafe {
+        gl::GetQueryObjectiv(id, pname, params);
+    };
+}
+
+pub fn GetQueryObjectui64v(id: GLuint, pname: GLenum, params: *mut GLuint64) {
+    unsafe {
+        gl::GetQueryObjectui64v(id, pname, params);
+    };
+}
+
+pub fn DeleteBuffers(n: GLsizei, buffers: *const GLuint) {
+    unsafe {
+        gl::DeleteBuffers(n, buffers);
+    }
+}
+
+pub fn DeleteVertexArrays(n: GLsizei, arrays: *const GLuint) {
+    unsafe {
+        gl::DeleteVertexArrays(n, arrays);
+    }
+}
+
+pub fn TexImage3D(target: GLenum, level: GLint, internal_format: GLint, width: GLsizei,
+                  height: GLsizei, depth: GLsizei, border: GLint, format: GLenum, type_: GLenum,
+                  pixels: *const c_void) {
+    unsafe {
+        gl::TexImage3D(target, level, internal_format, width, height, depth, border, format, type_,
+                       pixels);
+    }
+}
+
+pub fn TexSubImage3D(target: GLenum, level: GLint, x_offset: GLint, y_offset: GLint, z_offset: GLint,
+                     width: GLsizei, height: GLsizei, depth: GLsizei, format: GLenum, type_: GLenum,
+                     pixels: *const c_void) {
+    unsafe {
+        gl::TexSubImage3D(target, level, x_offset, y_offset, z_offset, width, height, depth, format,
+                          type_, pixels);
+    }
+}
+
+pub fn GetIntegerv(pname: GLenum, data: &mut GLint) {
+    unsafe {
+        gl::GetIntegerv(pname, data);
+    }
+}
+
+pub fn BufferStorage(target: GLenum, size: GLsizeiptr, data: *const c_void, flags: GLbitfield) {
+    unsafe {
+        gl::BufferStorage(target, size, data, flags);
+    }
+}
+
+pub fn MapBufferRange(target: GLenum, offset: GLintptr, length: GLsizeiptr, access: GLbitfield) -> *mut c_void {
+    unsafe {
+        return gl::MapBufferRange(target, offset, length, access);
+    }
+}
+
+pub fn BindBufferRange(target: GLenum, index: GLuint, buffer: GLuint, offset: GLintptr, size: GLsizeiptr) {
+    unsafe {
+        gl::BindBufferRange(target, index, buffer, offset, size);
+    }
+}
+
+pub fn FenceSync() -> GLsync {
+    unsafe {
+        return gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0);
+    }
+}
+
+/// Waits for a fence, flushing so it's sure to be signalled eventually. Returns false if it timed
+/// out, true once it's signalled or if waiting failed.
+pub fn ClientWaitSync(sync: GLsync, timeout_nanoseconds: GLuint64) -> bool {
+    unsafe {
+        return gl::ClientWaitSync(sync, gl::SYNC_FLUSH_COMMANDS_BIT, timeout_nanoseconds) != gl::TIMEOUT_EXPIRED;
+    }
+}
+
+pub fn DeleteSync(sync: GLsync) {
+    unsafe {
+        gl::DeleteSync(sync);
+    }
+}
+
+pub fn UniformBlockBinding(program_id: GLuint, block_index: GLuint, binding: GLuint) {
+    unsafe {
+        gl::UniformBlockBinding(program_id, block_index, binding);
+    }
+}
+
+pub fn ShaderStorageBlockBinding(program_id: GLuint, block_index: GLuint, binding: GLuint) {
+    unsafe {
+        gl::ShaderStorageBlockBinding(program_id, block_index, binding);
+    }
+}
+
+pub fn GenFramebuffers(n: GLsizei, framebuffers: *mut GLuint) {
+    unsafe {
+        gl::GenFramebuffers(n, framebuffers);
+    }
+}
+
+pub fn DeleteFramebuffers(n: GLsizei, framebuffers: *const GLuint) {
+    unsafe {
+        gl::DeleteFramebuffers(n, framebuffers);
+    }
+}
+
+pub fn BindFramebuffer(target: GLenum, framebuffer: GLuint) {
+    unsafe {
+        gl::BindFramebuffer(target, framebuffer);
+    }
+}
+
+pub fn FramebufferTexture2D(target: GLenum, attachment: GLenum, texture_target: GLenum, texture: GLuint,
+                            level: GLint) {
+    unsafe {
+        gl::FramebufferTexture2D(target, attachment, texture_target, texture, level);
+    }
+}
+
+pub fn BlitFramebuffer(source: (GLint, GLint, GLint, GLint), destination: (GLint, GLint, GLint, GLint),
+                       mask: GLbitfield, filter: GLenum) {
+    unsafe {
+        gl::BlitFramebuffer(source.0, source.1, source.2, source.3,
+                            destination.0, destination.1, destination.2, destination.3, mask, filter);
+    }
+}
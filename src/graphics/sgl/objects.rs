@@ -0,0 +1,483 @@
+use std::ffi::c_void;
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::ptr;
+
+use gl::types::{GLenum, GLint, GLintptr, GLsizei, GLsizeiptr, GLuint, GLuint64};
+
+use crate::graphics::sgl;
+
+/// Undoes a bind when dropped, so nothing is left bound after the scope using it. Can't outlive
+/// the object that was bound.
+#[must_use = "the binding is undone as soon as the guard is dropped"]
+#[derive(Debug)]
+pub struct BindGuard<'a> {
+    unbind: Unbind,
+    _object: PhantomData<&'a ()>,
+}
+
+#[derive(Debug)]
+enum Unbind {
+    Buffer(GLenum),
+    Texture { target: GLenum, unit: GLuint },
+    VertexArray,
+}
+
+impl Drop for BindGuard<'_> {
+    fn drop(&mut self) {
+        match self.unbind {
+            Unbind::Buffer(target) => sgl::BindBuffer(target, 0),
+            Unbind::Texture { target, unit } => {
+                sgl::ActiveTexture(gl::TEXTURE0 + unit);
+                sgl::BindTexture(target, 0);
+            }
+            Unbind::VertexArray => sgl::BindVertexArray(0),
+        }
+    }
+}
+
+/// Groups the GL calls made while it's alive under a name, e.g. a post processing pass, so
+/// debug messages and graphics debuggers show which pass they came from.
+#[must_use = "the group ends as soon as the guard is dropped"]
+#[derive(Debug)]
+pub struct DebugGroup {
+    _private: (),
+}
+
+impl DebugGroup {
+    pub fn push(name: &str) -> DebugGroup {
+        sgl::PushDebugGroup(name);
+        DebugGroup { _private: () }
+    }
+}
+
+impl Drop for DebugGroup {
+    fn drop(&mut self) {
+        sgl::PopDebugGroup();
+    }
+}
+
+/// A GL buffer holding elements of T, which must be plain data laid out the way the shader
+/// expects, e.g. a std140 struct for a uniform block.
+#[derive(Debug)]
+pub struct Buffer<T> {
+    id: GLuint,
+    target: GLenum,
+    usage: GLenum,
+    len: usize,  // In elements
+    _data: PhantomData<T>,
+}
+
+impl<T> Buffer<T> {
+    /// Room for len elements, with undefined contents until something is uploaded.
+    pub fn new(target: GLenum, len: usize, usage: GLenum) -> Buffer<T> {
+        Buffer::with_data(target, len, ptr::null(), usage)
+    }
+
+    pub fn from_slice(target: GLenum, data: &[T], usage: GLenum) -> Buffer<T> {
+        Buffer::with_data(target, data.len(), data.as_ptr() as *const c_void, usage)
+    }
+
+    /// Room for len elements in immutable storage that stays mapped for writing, for data
+    /// rewritten every frame. The mapping is coherent so writes need no flushing, and lives as
+    /// long as the buffer, but nothing stops writing over data the gpu is still reading.
+    pub fn persistent(target: GLenum, len: usize) -> (Buffer<T>, *mut T) {
+        let flags = gl::MAP_WRITE_BIT | gl::MAP_PERSISTENT_BIT | gl::MAP_COHERENT_BIT;
+        let size = (len * size_of::<T>()) as GLsizeiptr;
+        let mut id: GLuint = 0;
+        sgl::GenBuffers(1, &mut id);
+        sgl::BindBuffer(target, id);
+        sgl::BufferStorage(target, size, ptr::null(), flags);
+        let mapped = sgl::MapBufferRange(target, 0, size, flags) as *mut T;
+        sgl::BindBuffer(target, 0);
+        // Everything written through the pointer would go nowhere
+        assert!(!mapped.is_null(), "Failed to map a persistent buffer of {} bytes", size);
+        (Buffer { id, target, usage: gl::NONE, len, _data: PhantomData }, mapped)
+    }
+
+    fn with_data(target: GLenum, len: usize, data: *const c_void, usage: GLenum) -> Buffer<T> {
+        let mut id: GLuint = 0;
+        sgl::GenBuffers(1, &mut id);
+        sgl::BindBuffer(target, id);
+        sgl::BufferData(target, (len * size_of::<T>()) as GLsizeiptr, data, usage);
+        sgl::BindBuffer(target, 0);
+        Buffer { id, target, usage, len, _data: PhantomData }
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    /// Names the buffer in GL debug messages and graphics debuggers.
+    pub fn set_label(&self, label: &str) {
+        sgl::ObjectLabel(gl::BUFFER, self.id, label);
+    }
+
+    pub fn target(&self) -> GLenum {
+        self.target
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn bind(&self) -> BindGuard<'_> {
+        sgl::BindBuffer(self.target, self.id);
+        BindGuard { unbind: Unbind::Buffer(self.target), _object: PhantomData }
+    }
+
+    /// Binds to an indexed binding point, the binding=N of a uniform or storage block. Only
+    /// those targets have binding points, so this panics for any other.
+    pub fn bind_base(&self, index: GLuint) {
+        assert!(matches!(self.target, gl::UNIFORM_BUFFER | gl::SHADER_STORAGE_BUFFER
+                                      | gl::ATOMIC_COUNTER_BUFFER | gl::TRANSFORM_FEEDBACK_BUFFER),
+                "Buffer target {:#x} has no indexed binding points", self.target);
+        sgl::BindBufferBase(self.target, index, self.id);
+    }
+
+    /// Binds len elements from offset to an indexed binding point. The offset has to be a
+    /// multiple of the target's offset alignment, e.g. gl::UNIFORM_BUFFER_OFFSET_ALIGNMENT.
+    pub fn bind_range(&self, index: GLuint, offset: usize, len: usize) {
+        assert!(offset + len <= self.len, "Binding elements {}..{} of a buffer of {}", offset, offset + len,
+                self.len);
+        sgl::BindBufferRange(self.target, index, self.id, (offset * size_of::<T>()) as GLintptr,
+                             (len * size_of::<T>()) as GLsizeiptr);
+    }
+
+    /// Replaces the whole contents, resizing the buffer if data is a different length.
+    pub fn upload(&mut self, data: &[T]) {
+        if data.len() != self.len {
+            assert!(self.usage != gl::NONE, "Persistent buffers can't be resized");
+            sgl::BindBuffer(self.target, self.id);
+            sgl::BufferData(self.target, size_of_val(data) as GLsizeiptr,
+                            data.as_ptr() as *const c_void, self.usage);
+            sgl::BindBuffer(self.target, 0);
+            self.len = data.len();
+        } else {
+            self.update(0, data);
+        }
+    }
+
+    /// Overwrites elements from offset, panicking if they'd go past the end.
+    pub fn update(&self, offset: usize, data: &[T]) {
+        assert!(offset + data.len() <= self.len, "Writing elements {}..{} of a buffer of {}", offset,
+                offset + data.len(), self.len);
+        let _bound = self.bind();
+        sgl::BufferSubData(self.target, (offset * size_of::<T>()) as GLintptr,
+                           size_of_val(data) as GLsizeiptr, data.as_ptr() as *const c_void);
+    }
+
+    /// Copies every element back from the gpu. Shader writes need a
+    /// gl::BUFFER_UPDATE_BARRIER_BIT barrier first to be seen.
+    pub fn read(&self) -> Vec<T> where T: Copy {
+        let mut data = Vec::with_capacity(self.len);
+        let _bound = self.bind();
+        sgl::GetBufferSubData(self.target, 0, (self.len * size_of::<T>()) as GLsizeiptr,
+                              data.as_mut_ptr() as *mut c_void);
+        unsafe { data.set_len(self.len) };  // Every byte was just written by GL
+        data
+    }
+}
+
+impl<T> Drop for Buffer<T> {
+    fn drop(&mut self) {
+        sgl::DeleteBuffers(1, &self.id);
+    }
+}
+
+/// A 2D texture with a fixed size and internal format.
+#[derive(Debug)]
+pub struct Texture2D {
+    id: GLuint,
+    width: u32,
+    height: u32,
+    format: GLenum,  // Internal format, e.g. gl::RGBA32F
+}
+
+impl Texture2D {
+    /// Clamped to the edge, filtered linearly when minified and nearest when magnified.
+    pub fn new(width: u32, height: u32, format: GLenum) -> Texture2D {
+        let mut id: GLuint = 0;
+        sgl::GenTextures(1, &mut id);
+        sgl::BindTexture(gl::TEXTURE_2D, id);
+        set_default_parameters(gl::TEXTURE_2D);
+        sgl::TexImage2D(gl::TEXTURE_2D, 0, format as GLint, width as GLsizei, height as GLsizei, 0,
+                        gl::RGBA, gl::FLOAT, ptr::null());
+        Texture2D { id, width, height, format }
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub fn set_label(&self, label: &str) {
+        sgl::ObjectLabel(gl::TEXTURE, self.id, label);
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    pub fn format(&self) -> GLenum {
+        self.format
+    }
+
+    pub fn set_filter(&self, min: GLenum, mag: GLenum) {
+        let _bound = self.bind(0);
+        sgl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, min as GLint);
+        sgl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, mag as GLint);
+    }
+
+    /// Binds for sampling by a sampler uniform with binding=unit.
+    pub fn bind(&self, unit: GLuint) -> BindGuard<'_> {
+        sgl::ActiveTexture(gl::TEXTURE0 + unit);
+        sgl::BindTexture(gl::TEXTURE_2D, self.id);
+        BindGuard { unbind: Unbind::Texture { target: gl::TEXTURE_2D, unit }, _object: PhantomData }
+    }
+
+    /// Binds for image load / store by an image uniform with binding=unit, in the texture's own
+    /// format.
+    pub fn bind_image(&self, unit: GLuint, access: GLenum) {
+        sgl::BindImageTexture(unit, self.id, 0, gl::FALSE, 0, access, self.format);
+    }
+
+    /// Replaces the whole texture with tightly packed RGBA8 pixels, first row at the bottom.
+    pub fn upload_rgba8(&self, pixels: &[u8]) {
+        assert_eq!(pixels.len(), (self.width * self.height * 4) as usize, "Wrong number of pixels");
+        let _bound = self.bind(0);
+        sgl::TexSubImage2D(gl::TEXTURE_2D, 0, 0, 0, self.width as GLsizei, self.height as GLsizei,
+                           gl::RGBA, gl::UNSIGNED_BYTE, pixels.as_ptr() as *const c_void);
+    }
+
+    /// Replaces the whole texture with tightly packed RGBA float pixels, first row at the bottom.
+    pub fn upload_rgba32f(&self, pixels: &[f32]) {
+        assert_eq!(pixels.len(), (self.width * self.height * 4) as usize, "Wrong number of pixels");
+        let _bound = self.bind(0);
+        sgl::TexSubImage2D(gl::TEXTURE_2D, 0, 0, 0, self.width as GLsizei, self.height as GLsizei,
+                           gl::RGBA, gl::FLOAT, pixels.as_ptr() as *const c_void);
+    }
+
+    /// Reads back every pixel as RGBA floats, first row at the bottom, including
+    /// anything shaders stored to it as an image.
+    pub fn read_rgba32f(&self) -> Vec<f32> {
+        let mut pixels = vec![0.0f32; (self.width * self.height * 4) as usize];
+        let _bound = self.bind(0);
+        sgl::MemoryBarrier(gl::TEXTURE_UPDATE_BARRIER_BIT);
+        sgl::GetTexImage(gl::TEXTURE_2D, 0, gl::RGBA, gl::FLOAT, pixels.as_mut_ptr() as *mut c_void);
+        pixels
+    }
+
+    /// Reads back every pixel as RGBA bytes, first row at the bottom, including
+    /// anything shaders stored to it as an image.
+    pub fn read_rgba8(&self) -> Vec<u8> {
+        let mut pixels = vec![0u8; (self.width * self.height * 4) as usize];
+        let _bound = self.bind(0);
+        sgl::MemoryBarrier(gl::TEXTURE_UPDATE_BARRIER_BIT);
+        sgl::GetTexImage(gl::TEXTURE_2D, 0, gl::RGBA, gl::UNSIGNED_BYTE, pixels.as_mut_ptr() as *mut c_void);
+        pixels
+    }
+}
+
+impl Drop for Texture2D {
+    fn drop(&mut self) {
+        sgl::DeleteTextures(1, &self.id);
+    }
+}
+
+/// Layers of 2D textures with the same size and format.
+#[derive(Debug)]
+pub struct TextureArray {
+    id: GLuint,
+    width: u32,
+    height: u32,
+    layers: u32,
+    format: GLenum,
+}
+
+impl TextureArray {
+    pub fn new(width: u32, height: u32, layers: u32, format: GLenum) -> TextureArray {
+        let mut id: GLuint = 0;
+        sgl::GenTextures(1, &mut id);
+        sgl::BindTexture(gl::TEXTURE_2D_ARRAY, id);
+        set_default_parameters(gl::TEXTURE_2D_ARRAY);
+        sgl::TexImage3D(gl::TEXTURE_2D_ARRAY, 0, format as GLint, width as GLsizei, height as GLsizei,
+                        layers as GLsizei, 0, gl::RGBA, gl::FLOAT, ptr::null());
+        TextureArray { id, width, height, layers, format }
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub fn set_label(&self, label: &str) {
+        sgl::ObjectLabel(gl::TEXTURE, self.id, label);
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    pub fn layers(&self) -> u32 {
+        self.layers
+    }
+
+    pub fn bind(&self, unit: GLuint) -> BindGuard<'_> {
+        sgl::ActiveTexture(gl::TEXTURE0 + unit);
+        sgl::BindTexture(gl::TEXTURE_2D_ARRAY, self.id);
+        BindGuard { unbind: Unbind::Texture { target: gl::TEXTURE_2D_ARRAY, unit }, _object: PhantomData }
+    }
+
+    /// Binds every layer for image load / store by an image2DArray uniform with binding=unit.
+    pub fn bind_image(&self, unit: GLuint, access: GLenum) {
+        sgl::BindImageTexture(unit, self.id, 0, gl::TRUE, 0, access, self.format);
+    }
+
+    /// Replaces one layer with tightly packed RGBA8 pixels, panicking if the layer is out of range.
+    pub fn upload_layer_rgba8(&self, layer: u32, pixels: &[u8]) {
+        assert!(layer < self.layers, "Layer {} of {}", layer, self.layers);
+        assert_eq!(pixels.len(), (self.width * self.height * 4) as usize, "Wrong number of pixels");
+        let _bound = self.bind(0);
+        sgl::TexSubImage3D(gl::TEXTURE_2D_ARRAY, 0, 0, 0, layer as GLint, self.width as GLsizei,
+                           self.height as GLsizei, 1, gl::RGBA, gl::UNSIGNED_BYTE,
+                           pixels.as_ptr() as *const c_void);
+    }
+}
+
+impl Drop for TextureArray {
+    fn drop(&mut self) {
+        sgl::DeleteTextures(1, &self.id);
+    }
+}
+
+#[derive(Debug)]
+pub struct VertexArray {
+    id: GLuint,
+}
+
+impl VertexArray {
+    pub fn new() -> VertexArray {
+        let mut id: GLuint = 0;
+        sgl::GenVertexArrays(1, &mut id);
+        VertexArray { id }
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub fn bind(&self) -> BindGuard<'_> {
+        sgl::BindVertexArray(self.id);
+        BindGuard { unbind: Unbind::VertexArray, _object: PhantomData }
+    }
+}
+
+impl Default for VertexArray {
+    fn default() -> VertexArray {
+        VertexArray::new()
+    }
+}
+
+impl Drop for VertexArray {
+    fn drop(&mut self) {
+        sgl::DeleteVertexArrays(1, &self.id);
+    }
+}
+
+/// A framebuffer for copying textures to the window with a blit, which unlike drawing a quad
+/// needs no shaders.
+#[derive(Debug)]
+pub struct Framebuffer {
+    id: GLuint,
+}
+
+impl Framebuffer {
+    pub fn new() -> Framebuffer {
+        let mut id: GLuint = 0;
+        sgl::GenFramebuffers(1, &mut id);
+        Framebuffer { id }
+    }
+
+    /// Stretches texture over the default framebuffer, which is window_size big.
+    pub fn blit_to_window(&self, texture: &Texture2D, window_size: (u32, u32)) {
+        sgl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.id);
+        sgl::FramebufferTexture2D(gl::READ_FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, texture.id(), 0);
+        sgl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, 0);
+        sgl::BlitFramebuffer((0, 0, texture.width as GLint, texture.height as GLint),
+                             (0, 0, window_size.0 as GLint, window_size.1 as GLint),
+                             gl::COLOR_BUFFER_BIT, gl::NEAREST);
+        sgl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+    }
+}
+
+impl Default for Framebuffer {
+    fn default() -> Framebuffer {
+        Framebuffer::new()
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        sgl::DeleteFramebuffers(1, &self.id);
+    }
+}
+
+/// A query object for one target, e.g. gl::TIME_ELAPSED.
+#[derive(Debug)]
+pub struct Query {
+    id: GLuint,
+    target: GLenum,
+}
+
+impl Query {
+    pub fn new(target: GLenum) -> Query {
+        let mut id: GLuint = 0;
+        sgl::GenQueries(1, &mut id);
+        Query { id, target }
+    }
+
+    /// Only one query per target can be active at a time.
+    pub fn begin(&self) {
+        sgl::BeginQuery(self.target, self.id);
+    }
+
+    pub fn end(&self) {
+        sgl::EndQuery(self.target);
+    }
+
+    /// The result if the gpu has finished with the query, without waiting for it.
+    pub fn result(&self) -> Option<GLuint64> {
+        let mut available: GLint = 0;
+        sgl::GetQueryObjectiv(self.id, gl::QUERY_RESULT_AVAILABLE, &mut available);
+        if available == 0 {
+            return None;
+        }
+        let mut result: GLuint64 = 0;
+        sgl::GetQueryObjectui64v(self.id, gl::QUERY_RESULT, &mut result);
+        Some(result)
+    }
+}
+
+impl Drop for Query {
+    fn drop(&mut self) {
+        sgl::DeleteQueries(1, &self.id);
+    }
+}
+
+fn set_default_parameters(target: GLenum) {
+    sgl::TexParameteri(target, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
+    sgl::TexParameteri(target, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+    sgl::TexParameteri(target, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+    sgl::TexParameteri(target, gl::TEXTURE_MAG_FILTER, gl::NEAREST as GLint);
+}
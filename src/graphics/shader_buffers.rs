@@ -0,0 +1,276 @@
+use std::ffi::c_void;
+use std::mem::size_of;
+use std::ops::Range;
+use std::ptr;
+use std::sync::Mutex;
+
+use gl::types::{GLenum, GLint, GLintptr, GLsizeiptr, GLsync, GLuint};
+
+use crate::graphics::{sgl, Buffer};
+
+const RING_SLOTS: usize = 3;  // Frames a streamed buffer can be behind the gpu before waiting
+const FENCE_TIMEOUT: u64 = 1_000_000_000;  // Nanoseconds, waits are retried after this
+
+/// A struct that can be uploaded to a uniform block, laid out with #[std140::repr_std140].
+pub trait Std140: std140::Std140Struct {}
+
+impl<T: std140::Std140Struct> Std140 for T {}
+
+/// Plain data laid out the way GLSL lays out the matching type in a std430 storage block.
+///
+/// # Safety
+/// Every field must be at the offset std430 gives it, e.g. a vec3 is 16 byte aligned, and the
+/// size must be a multiple of the type's std430 alignment so arrays line up.
+pub unsafe trait Std430: Copy {}
+
+unsafe impl Std430 for u32 {}
+unsafe impl Std430 for i32 {}
+unsafe impl Std430 for f32 {}
+unsafe impl<T: Std430, const N: usize> Std430 for [T; N] {}
+
+// Bit masks of the binding points in use, shared between all programs
+static UNIFORM_BINDINGS: Mutex<u64> = Mutex::new(0);
+static STORAGE_BINDINGS: Mutex<u64> = Mutex::new(0);
+
+/// An indexed binding point of a target, given back when dropped.
+#[derive(Debug)]
+struct Binding {
+    target: GLenum,
+    index: GLuint,
+}
+
+impl Binding {
+    /// The lowest binding point not already in use. Panics if they've all been taken.
+    fn allocate(target: GLenum) -> Binding {
+        let (bindings, max_name) = binding_points(target);
+        let mut max: GLint = 0;
+        sgl::GetIntegerv(max_name, &mut max);
+
+        let mut used = bindings.lock().unwrap();
+        let index = (!*used).trailing_zeros();
+        assert!(index < (max as u32).min(u64::BITS), "Out of binding points for buffer target {:#x}", target);
+        *used |= 1 << index;
+        Binding { target, index }
+    }
+}
+
+impl Drop for Binding {
+    fn drop(&mut self) {
+        let (bindings, _) = binding_points(self.target);
+        *bindings.lock().unwrap() &= !(1 << self.index);
+    }
+}
+
+fn binding_points(target: GLenum) -> (&'static Mutex<u64>, GLenum) {
+    match target {
+        gl::UNIFORM_BUFFER => (&UNIFORM_BINDINGS, gl::MAX_UNIFORM_BUFFER_BINDINGS),
+        gl::SHADER_STORAGE_BUFFER => (&STORAGE_BINDINGS, gl::MAX_SHADER_STORAGE_BUFFER_BINDINGS),
+        _ => panic!("Buffer target {:#x} has no binding points we allocate", target),
+    }
+}
+
+/// Adds a byte or element range to a dirty range, giving the smallest range covering both.
+fn extend_dirty(dirty: &mut Option<Range<usize>>, range: Range<usize>) {
+    *dirty = Some(match dirty.take() {
+        Some(dirty) => dirty.start.min(range.start)..dirty.end.max(range.end),
+        None => range,
+    });
+}
+
+#[derive(Debug)]
+enum UniformStorage {
+    Static(Buffer<u8>),
+    // Each flush writes the next slot, so the gpu can still be reading the ones before it
+    Ring {
+        buffer: Buffer<u8>,
+        mapped: *mut u8,
+        stride: usize,  // Bytes between slots, to keep each at the offset alignment
+        slot: usize,  // The one the gpu was last given
+        fences: [Option<GLsync>; RING_SLOTS],  // Signalled once the gpu is done with each slot
+    },
+}
+
+/// A value of a std140 struct kept on the gpu for a uniform block, with its own binding point.
+/// Changes are made to a copy on the cpu and only uploaded by flush().
+#[derive(Debug)]
+pub struct UniformBuffer<T: Std140> {
+    value: T,
+    storage: UniformStorage,
+    dirty: Option<Range<usize>>,  // Bytes changed since the last flush
+    binding: Binding,
+}
+
+impl<T: Std140> UniformBuffer<T> {
+    /// For values that rarely change. Only the bytes that changed are uploaded.
+    pub fn new(value: T) -> UniformBuffer<T> {
+        let buffer = Buffer::new(gl::UNIFORM_BUFFER, size_of::<T>(), gl::DYNAMIC_DRAW);
+        UniformBuffer::with_storage(value, UniformStorage::Static(buffer))
+    }
+
+    /// For values that change every frame. Rather than waiting for the gpu to finish with the
+    /// buffer before changing it, each flush writes the whole value to the next part of a
+    /// persistently mapped ring.
+    pub fn streamed(value: T) -> UniformBuffer<T> {
+        let mut alignment: GLint = 0;
+        sgl::GetIntegerv(gl::UNIFORM_BUFFER_OFFSET_ALIGNMENT, &mut alignment);
+        let stride = size_of::<T>().next_multiple_of(alignment.max(1) as usize);
+        let (buffer, mapped) = Buffer::persistent(gl::UNIFORM_BUFFER, stride * RING_SLOTS);
+        UniformBuffer::with_storage(value, UniformStorage::Ring { buffer, mapped, stride, slot: 0,
+            fences: [None; RING_SLOTS] })
+    }
+
+    fn with_storage(value: T, storage: UniformStorage) -> UniformBuffer<T> {
+        let mut buffer = UniformBuffer { value, storage, dirty: Some(0..size_of::<T>()),
+            binding: Binding::allocate(gl::UNIFORM_BUFFER) };
+        buffer.flush();
+        buffer
+    }
+
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// The binding point to give the uniform block, see Program::bind_uniform_block.
+    pub fn binding(&self) -> GLuint {
+        self.binding.index
+    }
+
+    pub fn set_label(&self, label: &str) {
+        match &self.storage {
+            UniformStorage::Static(buffer) | UniformStorage::Ring { buffer, .. } => buffer.set_label(label),
+        }
+    }
+
+    pub fn set(&mut self, value: T) {
+        self.value = value;
+        self.dirty = Some(0..size_of::<T>());
+    }
+
+    /// Sets one field, so only its bytes are uploaded, e.g.
+    /// buffer.set_field(|settings| &mut settings.delta_time, float(delta_time)).
+    pub fn set_field<F>(&mut self, field: impl FnOnce(&mut T) -> &mut F, value: F) {
+        let base = &self.value as *const T as usize;
+        let field = field(&mut self.value);
+        let offset = field as *const F as usize - base;
+        assert!(offset + size_of::<F>() <= size_of::<T>(), "Field isn't part of the uniform buffer's value");
+        *field = value;
+        extend_dirty(&mut self.dirty, offset..offset + size_of::<F>());
+    }
+
+    /// Uploads what changed since the last flush and binds the buffer to its binding point.
+    pub fn flush(&mut self) {
+        let value = &self.value as *const T as *const u8;
+        match &mut self.storage {
+            UniformStorage::Static(buffer) => {
+                if let Some(dirty) = self.dirty.take() {
+                    let _bound = buffer.bind();
+                    sgl::BufferSubData(gl::UNIFORM_BUFFER, dirty.start as GLintptr, dirty.len() as GLsizeiptr,
+                                       value.wrapping_add(dirty.start) as *const c_void);
+                }
+                buffer.bind_base(self.binding.index);
+            }
+            UniformStorage::Ring { buffer, mapped, stride, slot, fences } => {
+                if self.dirty.take().is_some() {
+                    // Everything submitted so far is all that can be reading the current slot
+                    fences[*slot] = Some(sgl::FenceSync());
+                    *slot = (*slot + 1) % RING_SLOTS;
+                    if let Some(fence) = fences[*slot].take() {
+                        while !sgl::ClientWaitSync(fence, FENCE_TIMEOUT) {}
+                        sgl::DeleteSync(fence);
+                    }
+                    // The slot is part of the mapping and nothing on the gpu is reading it
+                    unsafe {
+                        ptr::copy_nonoverlapping(value, mapped.add(*slot * *stride), size_of::<T>());
+                    }
+                }
+                buffer.bind_range(self.binding.index, *slot * *stride, size_of::<T>());
+            }
+        }
+    }
+}
+
+impl<T: Std140> Drop for UniformBuffer<T> {
+    fn drop(&mut self) {
+        if let UniformStorage::Ring { fences, .. } = &mut self.storage {
+            fences.iter_mut().filter_map(Option::take).for_each(sgl::DeleteSync);
+        }
+    }
+}
+
+/// An array of std430 elements on the gpu for a storage block, with its own binding point.
+/// Changes are made to a copy on the cpu and only uploaded by flush(), so anything the shaders
+/// write to the buffer isn't read back.
+#[derive(Debug)]
+pub struct StorageBuffer<T: Std430> {
+    data: Vec<T>,
+    buffer: Buffer<T>,
+    dirty: Option<Range<usize>>,  // Elements changed since the last flush
+    binding: Binding,
+}
+
+impl<T: Std430> StorageBuffer<T> {
+    pub fn new(data: Vec<T>) -> StorageBuffer<T> {
+        let buffer = Buffer::from_slice(gl::SHADER_STORAGE_BUFFER, &data, gl::DYNAMIC_DRAW);
+        let storage_buffer = StorageBuffer { data, buffer, dirty: None,
+            binding: Binding::allocate(gl::SHADER_STORAGE_BUFFER) };
+        storage_buffer.buffer.bind_base(storage_buffer.binding.index);
+        storage_buffer
+    }
+
+    pub fn data(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// The binding point to give the storage block, see Program::bind_storage_block.
+    pub fn binding(&self) -> GLuint {
+        self.binding.index
+    }
+
+    /// Names the buffer in GL debug messages. Kept when the buffer is resized.
+    pub fn set_label(&self, label: &str) {
+        self.buffer.set_label(label);
+    }
+
+    pub fn set(&mut self, index: usize, value: T) {
+        self.write(index, &[value]);
+    }
+
+    /// Overwrites elements from offset, panicking if they'd go past the end.
+    pub fn write(&mut self, offset: usize, values: &[T]) {
+        self.data[offset..offset + values.len()].copy_from_slice(values);
+        extend_dirty(&mut self.dirty, offset..offset + values.len());
+    }
+
+    /// Replaces every element, which can change the length.
+    pub fn replace(&mut self, data: Vec<T>) {
+        self.dirty = Some(0..data.len());
+        self.data = data;
+    }
+
+    /// Reads the buffer back from the gpu, including what shaders stored to it, which the copy
+    /// data() gives doesn't have. Writes that haven't been flushed aren't included.
+    pub fn read_back(&self) -> Vec<T> {
+        sgl::MemoryBarrier(gl::BUFFER_UPDATE_BARRIER_BIT);
+        self.buffer.read()
+    }
+
+    /// Uploads what changed since the last flush and binds the buffer to its binding point.
+    pub fn flush(&mut self) {
+        if let Some(dirty) = self.dirty.take() {
+            if self.buffer.len() != self.data.len() {
+                self.buffer.upload(&self.data);
+            } else {
+                self.buffer.update(dirty.start, &self.data[dirty]);
+            }
+        }
+        self.buffer.bind_base(self.binding.index);
+    }
+}
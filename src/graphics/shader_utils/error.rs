@@ -0,0 +1,145 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::graphics::shader_utils::preprocessor::{find_line_reference, PreprocessedSource};
+
+const EXCERPT_CONTEXT: u32 = 2;  // Lines shown either side of the one with the error
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShaderStage {
+    Preprocess,
+    Vertex,
+    Fragment,
+    Compute,
+    Link,
+}
+
+impl fmt::Display for ShaderStage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            ShaderStage::Preprocess => "preprocessing",
+            ShaderStage::Vertex => "vertex shader",
+            ShaderStage::Fragment => "fragment shader",
+            ShaderStage::Compute => "compute shader",
+            ShaderStage::Link => "linking",
+        })
+    }
+}
+
+/// A shader that failed to preprocess, compile or link, pointing at the first error in the
+/// original file rather than the preprocessed source.
+#[derive(Debug, Clone)]
+pub struct ShaderError {
+    pub stage: ShaderStage,
+    pub file: &'static str,
+    pub line: Option<u32>,  // From 1, None if the driver didn't say
+    pub column: Option<u32>,  // From 1, only Mesa gives one
+    pub message: String,
+    pub log: String,  // The whole log, with line references mapped to their files
+    excerpt: Vec<(u32, String)>,  // Lines around the error with their numbers
+}
+
+impl ShaderError {
+    pub fn new(stage: ShaderStage, file: &'static str, message: String) -> ShaderError {
+        ShaderError { stage, file, line: None, column: None, log: message.clone(), message,
+            excerpt: Vec::new() }
+    }
+
+    /// An error at a line of a file, shown with the lines around it from source.
+    pub fn at_line(stage: ShaderStage, file: &'static str, source: &str, line: u32, message: String)
+                   -> ShaderError {
+        let lines: Vec<&str> = source.lines().collect();
+        let first = line.saturating_sub(EXCERPT_CONTEXT).max(1);
+        let last = (line + EXCERPT_CONTEXT).min(lines.len() as u32);
+        let excerpt = (first..=last).map(|number| (number, lines[number as usize - 1].to_string())).collect();
+        ShaderError { line: Some(line), excerpt, ..ShaderError::new(stage, file, message) }
+    }
+
+    /// Parses a driver's info log for the source that was compiled. Understands Mesa, NVIDIA
+    /// and AMD's formats, anything else is kept whole as the message.
+    pub fn from_log(stage: ShaderStage, file: &'static str, source: &PreprocessedSource, log: &str)
+                    -> ShaderError {
+        let log = log.trim_end_matches(['\0', ' ', '\n']);
+        let mapped_log = source.map_log(log);
+        let lines: Vec<&str> = log.lines().filter(|line| !line.trim().is_empty()).collect();
+        // Warnings come first in some logs, so prefer the first line that's an error
+        let Some(error_line) = lines.iter().find(|line| line.to_lowercase().contains("error"))
+            .or(lines.first()) else {
+            return ShaderError::new(stage, file, String::from("No info log"));
+        };
+
+        let Some((start, end, line)) = find_line_reference(error_line) else {
+            return ShaderError { log: mapped_log, ..ShaderError::new(stage, file, error_line.trim().to_string()) };
+        };
+        let rest = &error_line[end..];
+        let column = rest.strip_prefix('(')
+            .and_then(|rest| rest.split_once(')'))
+            .and_then(|(column, _)| column.parse().ok());
+        let message = error_message(&error_line[..start], rest);
+
+        let mut error = ShaderError::new(stage, file, message);
+        if let Some((original_file, original_line)) = source.original_line(line) {
+            error.file = original_file;
+            error.line = Some(original_line);
+            // The preprocessed line keeps its text, so the excerpt comes from around it, but
+            // only lines that came from the same file
+            let preprocessed: Vec<&str> = source.source.lines().collect();
+            let first = line.saturating_sub(EXCERPT_CONTEXT).max(1);
+            let last = (line + EXCERPT_CONTEXT).min(preprocessed.len() as u32);
+            error.excerpt = (first..=last)
+                .filter_map(|number| match source.original_line(number) {
+                    Some((excerpt_file, excerpt_line)) if excerpt_file == original_file =>
+                        Some((excerpt_line, preprocessed[number as usize - 1].to_string())),
+                    _ => None,
+                })
+                .collect();
+        }
+        ShaderError { column, log: mapped_log, ..error }
+    }
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, "{}:{}:{}", self.file, line, column)?,
+            (Some(line), None) => write!(f, "{}:{}", self.file, line)?,
+            _ => write!(f, "{}", self.file)?,
+        }
+        write!(f, ": {} error: {}", self.stage, self.message)?;
+
+        let width = self.excerpt.last().map_or(0, |(number, _)| number.to_string().len());
+        for (number, text) in &self.excerpt {
+            write!(f, "\n{:>width$} | {}", number, text, width = width)?;
+            if Some(*number) == self.line {
+                // Without a column, underline from the first non whitespace character
+                let indent = text.len() - text.trim_start().len();
+                let column = self.column.map_or(indent, |column| (column as usize).saturating_sub(1));
+                write!(f, "\n{:>width$} | {}^", "", " ".repeat(column), width = width)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Error for ShaderError {}
+
+/// The message part of a log line, without the line reference and severity around it, e.g.
+/// "syntax error, unexpected ';'" from "0:12(5): error: syntax error, unexpected ';'".
+fn error_message(before: &str, after: &str) -> String {
+    let after = after.strip_prefix('(').and_then(|rest| rest.split_once(')')).map_or(after, |(_, rest)| rest);
+    let mut message = after.trim_start_matches([':', ' ']);
+    for severity in ["error", "ERROR"] {
+        if let Some(rest) = message.strip_prefix(severity) {
+            message = rest.trim_start();
+            // NVIDIA adds a code like C1008
+            if let Some((code, rest)) = message.split_once(':') {
+                if code.starts_with('C') && code[1..].chars().all(|c| c.is_ascii_digit()) {
+                    message = rest;
+                }
+            }
+            message = message.trim_start_matches([':', ' ']);
+        }
+    }
+    let message = message.trim();
+    if message.is_empty() { before.trim().trim_end_matches(':').to_string() } else { message.to_string() }
+}
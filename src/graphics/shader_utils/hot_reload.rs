@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+
+const SHADER_EXTENSIONS: [&str; 4] = ["comp", "vert", "frag", "glsl"];
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+static DEV_MODE: AtomicBool = AtomicBool::new(false);
+
+/// In dev mode shader sources are read from the source tree instead of the copies baked into the
+/// binary, so they can be edited while the game runs. Set before creating anything with shaders.
+pub fn set_dev_mode(enabled: bool) {
+    DEV_MODE.store(enabled, Ordering::Relaxed);
+}
+
+pub fn dev_mode() -> bool {
+    DEV_MODE.load(Ordering::Relaxed)
+}
+
+/// A shader source file, use the shader_file! macro to make one.
+#[derive(Debug, Clone, Copy)]
+pub struct ShaderFile {
+    embedded: &'static str,  // Copy from when we were compiled
+    rust_file: &'static str,  // Rust source file the shader sits next to, relative to the crate
+    name: &'static str,
+}
+
+impl ShaderFile {
+    pub const fn new(embedded: &'static str, rust_file: &'static str, name: &'static str) -> ShaderFile {
+        ShaderFile { embedded, rust_file, name }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Where the file is in the source tree.
+    pub fn path(&self) -> PathBuf {
+        let rust_file = Path::new(env!("CARGO_MANIFEST_DIR")).join(self.rust_file);
+        rust_file.parent().unwrap_or(Path::new("")).join(self.name)
+    }
+
+    /// The file's contents, from disk in dev mode, falling back to the embedded copy if it can't
+    /// be read.
+    pub fn source(&self) -> String {
+        if dev_mode() {
+            match fs::read_to_string(self.path()) {
+                Ok(source) => return source,
+                Err(error) => println!("Failed to read {}, using the built in copy: {}",
+                                       self.path().display(), error),
+            }
+        }
+        self.embedded.to_string()
+    }
+}
+
+/// Makes a ShaderFile for a file next to the calling Rust source file, like include_str!.
+macro_rules! shader_file {
+    ($name:literal) => {
+        $crate::graphics::shader_utils::hot_reload::ShaderFile::new(include_str!($name), file!(), $name)
+    };
+}
+pub(crate) use shader_file;
+
+/// Polls the shader files in the source tree for changes.
+#[derive(Debug)]
+pub struct ShaderWatcher {
+    root: PathBuf,
+    modified: HashMap<PathBuf, SystemTime>,
+    last_poll: Instant,
+}
+
+impl ShaderWatcher {
+    pub fn new() -> ShaderWatcher {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let mut modified = HashMap::new();
+        collect_modified_times(&root, &mut modified);
+        ShaderWatcher { root, modified, last_poll: Instant::now() }
+    }
+
+    /// Whether any shader was saved since this last returned true. Only looks at the files every
+    /// POLL_INTERVAL, so it's cheap to call every frame.
+    pub fn changed(&mut self) -> bool {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return false;
+        }
+        self.last_poll = Instant::now();
+
+        let mut modified = HashMap::new();
+        collect_modified_times(&self.root, &mut modified);
+        let changed = modified != self.modified;
+        self.modified = modified;
+        changed
+    }
+}
+
+impl Default for ShaderWatcher {
+    fn default() -> ShaderWatcher {
+        ShaderWatcher::new()
+    }
+}
+
+fn collect_modified_times(directory: &Path, modified: &mut HashMap<PathBuf, SystemTime>) {
+    let Ok(entries) = fs::read_dir(directory) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_modified_times(&path, modified);
+            continue;
+        }
+        let is_shader = path.extension()
+            .is_some_and(|extension| SHADER_EXTENSIONS.iter().any(|shader| extension == *shader));
+        if let (true, Ok(time)) = (is_shader, entry.metadata().and_then(|metadata| metadata.modified())) {
+            modified.insert(path, time);
+        }
+    }
+}
@@ -0,0 +1,7 @@
+float min3(vec3 v) {
+    return min(min(v.x, v.y), v.z);
+}
+
+float max3(vec3 v) {
+    return max(max(v.x, v.y), v.z);
+}
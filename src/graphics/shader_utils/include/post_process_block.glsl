@@ -0,0 +1,16 @@
+// Settings shared by every post processing pass, matches PostProcessBuffer in post_processor.rs
+layout (std140) uniform PostProcessBlock {
+    int tone_mapper;
+    bool auto_exposure;
+    float exposure;
+    float exposure_compensation;
+    float min_log_luminance;
+    float log_luminance_range;
+    float adaptation_speed;
+    float delta_time;
+    uint pixel_count;
+    bool bloom;
+    float bloom_threshold;
+    float bloom_strength;
+    bool srgb_output;
+};
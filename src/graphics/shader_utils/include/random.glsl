@@ -0,0 +1,25 @@
+// Hash based random numbers, the state isn't advanced so the same state gives the same value
+
+uint NextRandom(uint state) {
+    state = state * 747796405 + 2891336453;
+    uint result = ((state >> ((state >> 28) + 4)) ^ state) * 277803737;
+    result = (result >> 22) ^ result;
+    return result;
+}
+
+float RandomValue(uint state) {
+    return float(NextRandom(state)) / 4294967295.0; // 2^32 - 1
+}
+
+float RandomValueNormalDistribution(uint state) {
+    float theta = 2 * 3.1415926 * RandomValue(state);
+    float rho = sqrt(float(-2 * log(RandomValue(state))));
+    return rho * cos(theta);
+}
+
+vec3 RandomDirection(uint state) {
+    float x = RandomValueNormalDistribution(state);
+    float y = RandomValueNormalDistribution(state);
+    float z = RandomValueNormalDistribution(state);
+    return normalize(vec3(x, y, z));
+}
@@ -1,8 +1,76 @@
 use std::ffi::CString;
 
+use gl::types::GLenum;
+
+use crate::graphics::shader_utils::error::{ShaderError, ShaderStage};
+use crate::graphics::shader_utils::hot_reload::ShaderFile;
+use crate::graphics::shader_utils::preprocessor::{preprocess, PreprocessedSource};
+use crate::graphics::shader_utils::program::Program;
+use crate::graphics::shader_utils::shader::Shader;
+
+pub mod error;
+pub mod hot_reload;
+pub mod preprocessor;
 pub mod program;
+pub mod program_cache;
 pub mod shader;
 
+/// Compiles a compute shader file into a program, preprocessed with the defines. Errors point
+/// at the original file and line. Loaded from the program cache if it's been built before.
+pub fn compute_program(file: ShaderFile, defines: &[(&str, String)]) -> Result<Program, ShaderError> {
+    let source = preprocess(file, defines)?;
+    let key = program_cache::cache_key(&[&source.source]);
+    let program = program_cache::load_or_build(file.name(), key, || {
+        let comp_shader = compile(file, &source, ShaderStage::Compute)?;
+        link(file, &[comp_shader])
+    })?;
+    program.set_label(file.name());
+    Ok(program)
+}
+
+/// Compiles a vertex and fragment shader file into a program, both preprocessed with the defines.
+pub fn graphics_program(vert_file: ShaderFile, frag_file: ShaderFile, defines: &[(&str, String)])
+                        -> Result<Program, ShaderError> {
+    let vert_source = preprocess(vert_file, defines)?;
+    let frag_source = preprocess(frag_file, defines)?;
+    let key = program_cache::cache_key(&[&vert_source.source, &frag_source.source]);
+    let program = program_cache::load_or_build(vert_file.name(), key, || {
+        let vert_shader = compile(vert_file, &vert_source, ShaderStage::Vertex)?;
+        let frag_shader = compile(frag_file, &frag_source, ShaderStage::Fragment)?;
+        link(vert_file, &[vert_shader, frag_shader])
+    })?;
+    program.set_label(&format!("{} + {}", vert_file.name(), frag_file.name()));
+    Ok(program)
+}
+
+/// Checks a program has the uniforms and blocks the code sets, see Program::require. A mismatch is
+/// reported like a link error, so reloading keeps the old program.
+pub fn require_interface(program: Program, file: ShaderFile, uniforms: &[(&str, GLenum)],
+                         blocks: &[&str]) -> Result<Program, ShaderError> {
+    program.require(uniforms, blocks)
+        .map_err(|error| ShaderError::new(ShaderStage::Link, file.name(), error.to_string()))?;
+    Ok(program)
+}
+
+fn compile(file: ShaderFile, preprocessed: &PreprocessedSource, stage: ShaderStage) -> Result<Shader, ShaderError> {
+    let source = CString::new(preprocessed.source.as_str())
+        .map_err(|error| ShaderError::new(ShaderStage::Preprocess, file.name(), error.to_string()))?;
+    let shader = match stage {
+        ShaderStage::Vertex => Shader::from_vert_source(&source),
+        ShaderStage::Fragment => Shader::from_frag_source(&source),
+        _ => Shader::from_comp_source(&source),
+    };
+    shader.map_err(|log| ShaderError::from_log(stage, file.name(), preprocessed, &log))
+}
+
+/// Link logs don't reliably point at lines, so they're kept whole.
+fn link(file: ShaderFile, shaders: &[Shader]) -> Result<Program, ShaderError> {
+    Program::from_shaders(shaders).map_err(|log| {
+        let log = log.trim_end_matches(['\0', ' ', '\n']).to_string();
+        ShaderError::new(ShaderStage::Link, file.name(), log)
+    })
+}
+
 fn create_whitespace_cstring_with_len(len: usize) -> CString {
     // allocate buffer of correct size
     let mut buffer: Vec<u8> = Vec::with_capacity(len + 1);
@@ -0,0 +1,118 @@
+use std::path::Path;
+
+use crate::graphics::shader_utils::error::{ShaderError, ShaderStage};
+use crate::graphics::shader_utils::hot_reload::{shader_file, ShaderFile};
+
+const DEFINES_NAME: &str = "<defines>";  // Where injected defines show up in the line map
+
+/// Files shaders can #include, looked up by file name. Add new ones here.
+const INCLUDES: [ShaderFile; 3] = [
+    shader_file!("include/math.glsl"),
+    shader_file!("include/post_process_block.glsl"),
+    shader_file!("include/random.glsl"),
+];
+
+/// A shader's source after preprocessing, ready to compile.
+#[derive(Debug, Clone)]
+pub struct PreprocessedSource {
+    pub source: String,
+    lines: Vec<(&'static str, u32)>,  // File name and line, from 1, for each line of source
+}
+
+impl PreprocessedSource {
+    /// Where a line of the preprocessed source, counting from 1, came from.
+    pub fn original_line(&self, line: u32) -> Option<(&'static str, u32)> {
+        self.lines.get((line as usize).checked_sub(1)?).copied()
+    }
+
+    /// Rewrites the line references in a compile log to the file and line they came from, so
+    /// "0:181(5): error" becomes "ray_tracer.comp:181(5): error". Understands the Mesa (0:181),
+    /// NVIDIA (0(181)) and AMD (ERROR: 0:181:) formats, other lines are left alone.
+    pub fn map_log(&self, log: &str) -> String {
+        log.lines().map(|line| match find_line_reference(line) {
+            Some((start, end, number)) => match self.original_line(number) {
+                Some((file, original)) => format!("{}{}:{}{}", &line[..start], file, original, &line[end..]),
+                None => line.to_string(),
+            },
+            None => line.to_string(),
+        }).collect::<Vec<_>>().join("\n")
+    }
+}
+
+/// Expands #include "file.glsl" lines with files from INCLUDES and adds defines straight after
+/// the #version line. Each file is only included once, so includes don't need guards and can't
+/// loop.
+pub fn preprocess(file: ShaderFile, defines: &[(&str, String)]) -> Result<PreprocessedSource, ShaderError> {
+    let mut output = PreprocessedSource { source: String::new(), lines: Vec::new() };
+    let mut included = Vec::new();
+    expand(file, &mut output, &mut included)?;
+
+    let version_line = output.source.lines().position(|line| line.trim_start().starts_with("#version"));
+    let insert_at = version_line.map_or(0, |line| line + 1);
+    let mut lines: Vec<&str> = output.source.lines().collect();
+    let define_lines: Vec<String> = defines.iter()
+        .map(|(name, value)| format!("#define {} {}", name, value))
+        .collect();
+    lines.splice(insert_at..insert_at, define_lines.iter().map(String::as_str));
+    output.lines.splice(insert_at..insert_at,
+                        (1..=define_lines.len() as u32).map(|line| (DEFINES_NAME, line)));
+    output.source = lines.join("\n") + "\n";
+    Ok(output)
+}
+
+fn expand(file: ShaderFile, output: &mut PreprocessedSource, included: &mut Vec<&'static str>)
+          -> Result<(), ShaderError> {
+    included.push(file.name());
+    let source = file.source();
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index as u32 + 1;
+        let Some(rest) = line.trim_start().strip_prefix("#include") else {
+            output.source.push_str(line);
+            output.source.push('\n');
+            output.lines.push((file.name(), line_number));
+            continue;
+        };
+
+        let name = rest.trim().strip_prefix('"').and_then(|name| name.strip_suffix('"'))
+            .ok_or_else(|| ShaderError::at_line(ShaderStage::Preprocess, file.name(), &source, line_number,
+                                                String::from("#include needs a file name in quotes")))?;
+        let include = INCLUDES.iter().find(|include| Path::new(include.name()).file_name()
+            .is_some_and(|file_name| file_name == name))
+            .ok_or_else(|| ShaderError::at_line(ShaderStage::Preprocess, file.name(), &source, line_number,
+                                                format!("Unknown include \"{}\"", name)))?;
+
+        if included.contains(&include.name()) {
+            // Keep the line so line numbers after it stay the same
+            output.source.push('\n');
+            output.lines.push((file.name(), line_number));
+        } else {
+            expand(*include, output, included)?;
+        }
+    }
+    Ok(())
+}
+
+/// The first "0:LINE" or "0(LINE)" in a log line, as its byte range and LINE. The leading
+/// number is the source string, which is always 0 as we pass one string.
+pub(crate) fn find_line_reference(line: &str) -> Option<(usize, usize, u32)> {
+    let bytes = line.as_bytes();
+    for start in 0..bytes.len() {
+        if bytes[start] != b'0' || (start > 0 && bytes[start - 1] != b' ') {
+            continue;
+        }
+        let (open, close) = match bytes.get(start + 1) {
+            Some(b':') => (start + 2, None),
+            Some(b'(') => (start + 2, Some(b')')),
+            _ => continue,
+        };
+        let digits = bytes[open..].iter().take_while(|byte| byte.is_ascii_digit()).count();
+        let Ok(number) = line[open..open + digits].parse() else { continue };
+        let end = open + digits;
+        match close {
+            Some(close) if bytes.get(end) == Some(&close) => return Some((start, end + 1, number)),
+            Some(_) => continue,
+            None => return Some((start, end, number)),
+        }
+    }
+    None
+}
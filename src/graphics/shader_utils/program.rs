@@ -1,11 +1,193 @@
-use gl::types::GLint;
+use std::error::Error;
+use std::fmt;
+
+use gl::types::{GLenum, GLint, GLuint};
 
 use crate::graphics::sgl;
 use crate::graphics::shader_utils::create_whitespace_cstring_with_len;
 use crate::graphics::shader_utils::shader::Shader;
+use crate::maths::matrix::Mat4;
+use crate::maths::vector::Vec3;
+
+/// An active uniform outside of any block, including samplers and images.
+#[derive(Debug, Clone)]
+pub struct UniformInfo {
+    pub name: String,
+    pub location: GLint,
+    pub kind: GLenum,  // GL type, e.g. gl::FLOAT_VEC2
+    pub array_size: GLint,
+    pub binding: Option<GLint>,  // Texture or image unit, for samplers and images
+}
+
+/// An active uniform block or shader storage block.
+#[derive(Debug, Clone)]
+pub struct BlockInfo {
+    pub name: String,
+    pub index: GLuint,  // Block index, for setting its binding
+    pub binding: GLuint,
+    pub size: GLint,  // In bytes, for storage blocks without the variable length array
+}
+
+/// What a program uses, read once after linking.
+#[derive(Debug, Clone, Default)]
+pub struct ProgramReflection {
+    pub uniforms: Vec<UniformInfo>,
+    pub uniform_blocks: Vec<BlockInfo>,
+    pub storage_blocks: Vec<BlockInfo>,
+}
+
+impl ProgramReflection {
+    fn read(program_id: GLuint) -> ProgramReflection {
+        let uniforms = (0..resource_count(program_id, gl::UNIFORM))
+            .filter_map(|index| {
+                let [location, kind, array_size, block_index] = resource_properties(
+                    program_id, gl::UNIFORM, index,
+                    [gl::LOCATION, gl::TYPE, gl::ARRAY_SIZE, gl::BLOCK_INDEX]);
+                if block_index != -1 {
+                    return None;  // Set through the block's buffer
+                }
+                let kind = kind as GLenum;
+                let binding = (is_sampler(kind) || is_image(kind)).then(|| {
+                    let mut unit: GLint = 0;
+                    sgl::GetUniformiv(program_id, location, &mut unit);
+                    unit
+                });
+                Some(UniformInfo { name: resource_name(program_id, gl::UNIFORM, index), location, kind,
+                    array_size, binding })
+            })
+            .collect();
+
+        let blocks = |interface: GLenum| (0..resource_count(program_id, interface))
+            .map(|index| {
+                let [binding, size] = resource_properties(program_id, interface, index,
+                                                          [gl::BUFFER_BINDING, gl::BUFFER_DATA_SIZE]);
+                BlockInfo { name: resource_name(program_id, interface, index), index,
+                    binding: binding as GLuint, size }
+            })
+            .collect();
+
+        ProgramReflection { uniforms, uniform_blocks: blocks(gl::UNIFORM_BLOCK),
+            storage_blocks: blocks(gl::SHADER_STORAGE_BLOCK) }
+    }
+
+    pub fn uniform(&self, name: &str) -> Option<&UniformInfo> {
+        self.uniforms.iter().find(|uniform| uniform.name == name)
+    }
+
+    /// Image uniforms, which are bound with glBindImageTexture rather than set.
+    pub fn images(&self) -> impl Iterator<Item = &UniformInfo> {
+        self.uniforms.iter().filter(|uniform| is_image(uniform.kind))
+    }
+
+    pub fn samplers(&self) -> impl Iterator<Item = &UniformInfo> {
+        self.uniforms.iter().filter(|uniform| is_sampler(uniform.kind))
+    }
+}
+
+/// Setting a uniform that the program doesn't have, or with the wrong type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UniformError {
+    // The compiler removes uniforms that aren't used, so this can also mean it's unused
+    NotFound(String),
+    BlockNotFound(String),
+    WrongType { name: String, expected: &'static str, found: &'static str },
+}
+
+impl fmt::Display for UniformError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UniformError::NotFound(name) => write!(f, "No active uniform called {}", name),
+            UniformError::BlockNotFound(name) => write!(f, "No active block called {}", name),
+            UniformError::WrongType { name, expected, found } =>
+                write!(f, "Uniform {} is a {}, not a {}", name, expected, found),
+        }
+    }
+}
+
+impl Error for UniformError {}
+
+/// A Rust value that can be written to a GLSL uniform of type KIND.
+pub trait UniformValue {
+    const KIND: GLenum;
+
+    fn write(&self, program_id: GLuint, location: GLint);
+}
+
+impl UniformValue for f32 {
+    const KIND: GLenum = gl::FLOAT;
+
+    fn write(&self, program_id: GLuint, location: GLint) {
+        sgl::ProgramUniform1f(program_id, location, *self);
+    }
+}
+
+impl UniformValue for (f32, f32) {
+    const KIND: GLenum = gl::FLOAT_VEC2;
+
+    fn write(&self, program_id: GLuint, location: GLint) {
+        sgl::ProgramUniform2f(program_id, location, self.0, self.1);
+    }
+}
+
+impl UniformValue for Vec3 {
+    const KIND: GLenum = gl::FLOAT_VEC3;
+
+    fn write(&self, program_id: GLuint, location: GLint) {
+        sgl::ProgramUniform3f(program_id, location, self.x, self.y, self.z);
+    }
+}
+
+impl UniformValue for [f32; 4] {
+    const KIND: GLenum = gl::FLOAT_VEC4;
+
+    fn write(&self, program_id: GLuint, location: GLint) {
+        sgl::ProgramUniform4f(program_id, location, self[0], self[1], self[2], self[3]);
+    }
+}
+
+impl UniformValue for i32 {
+    const KIND: GLenum = gl::INT;
+
+    fn write(&self, program_id: GLuint, location: GLint) {
+        sgl::ProgramUniform1i(program_id, location, *self);
+    }
+}
+
+impl UniformValue for (i32, i32) {
+    const KIND: GLenum = gl::INT_VEC2;
+
+    fn write(&self, program_id: GLuint, location: GLint) {
+        sgl::ProgramUniform2i(program_id, location, self.0, self.1);
+    }
+}
+
+impl UniformValue for u32 {
+    const KIND: GLenum = gl::UNSIGNED_INT;
+
+    fn write(&self, program_id: GLuint, location: GLint) {
+        sgl::ProgramUniform1ui(program_id, location, *self);
+    }
+}
+
+impl UniformValue for bool {
+    const KIND: GLenum = gl::BOOL;
+
+    fn write(&self, program_id: GLuint, location: GLint) {
+        sgl::ProgramUniform1i(program_id, location, *self as GLint);
+    }
+}
+
+impl UniformValue for Mat4 {
+    const KIND: GLenum = gl::FLOAT_MAT4;
+
+    fn write(&self, program_id: GLuint, location: GLint) {
+        sgl::ProgramUniformMatrix4fv(program_id, location, &self.columns);
+    }
+}
 
 pub struct Program {
     id: gl::types::GLuint,
+    reflection: ProgramReflection,
 }
 
 impl Program {
@@ -16,41 +198,196 @@ impl Program {
             sgl::AttachShader(program_id, shader)
         }
 
+        // Lets the program binary cache read it back
+        sgl::ProgramParameteri(program_id, gl::PROGRAM_BINARY_RETRIEVABLE_HINT, gl::TRUE as GLint);
         sgl::LinkProgram(program_id);
 
-        let mut success: GLint = 1;
-        sgl::GetProgramiv(program_id, gl::LINK_STATUS, &mut success);
-
-        if success == 0 {
-            let mut len: gl::types::GLint = 0;
-            sgl::GetProgramiv(program_id, gl::INFO_LOG_LENGTH, &mut len);
-
-            let error = create_whitespace_cstring_with_len(len as usize);
-            sgl::GetProgramInfoLog(
-                program_id,
-                len,
-                std::ptr::null_mut(),
-                error.as_ptr() as * mut gl::types::GLchar,
-            );
-
-
-            return Err(error.to_string_lossy().into_owned());
+        if let Err(error) = check_linked(program_id) {
+            sgl::DeleteProgram(program_id);
+            return Err(error);
         }
 
         for shader in shaders {
             sgl::DetachShader(program_id, shader.id());
         }
 
-        Ok(Program { id: program_id })
+        Ok(Program { id: program_id, reflection: ProgramReflection::read(program_id) })
+    }
+
+    /// Loads a program from a binary saved by binary(). Fails if the driver has changed since it
+    /// was saved.
+    pub fn from_binary(format: GLenum, binary: &[u8]) -> Result<Program, String> {
+        let program_id = sgl::CreateProgram();
+        sgl::ProgramBinary(program_id, format, binary);
+        if let Err(error) = check_linked(program_id) {
+            sgl::DeleteProgram(program_id);
+            return Err(error);
+        }
+        Ok(Program { id: program_id, reflection: ProgramReflection::read(program_id) })
+    }
+
+    /// The linked program in the driver's own format, None if it doesn't support that.
+    pub fn binary(&self) -> Option<(GLenum, Vec<u8>)> {
+        let (format, binary) = sgl::GetProgramBinary(self.id);
+        (!binary.is_empty()).then_some((format, binary))
     }
-    
+
     pub fn set_used(&self) {
         sgl::UseProgram(self.id);
     }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    /// Names the program in GL debug messages, e.g. with the file it was built from.
+    pub fn set_label(&self, label: &str) {
+        sgl::ObjectLabel(gl::PROGRAM, self.id, label);
+    }
+
+    pub fn reflection(&self) -> &ProgramReflection {
+        &self.reflection
+    }
+
+    /// Sets a uniform by name, checking it exists and has the value's type. The program doesn't
+    /// need to be in use.
+    pub fn set<T: UniformValue>(&self, name: &str, value: T) -> Result<(), UniformError> {
+        let uniform = self.reflection.uniform(name)
+            .ok_or_else(|| UniformError::NotFound(name.to_string()))?;
+        if uniform.kind != T::KIND {
+            return Err(UniformError::WrongType { name: name.to_string(), expected: type_name(uniform.kind),
+                found: type_name(T::KIND) });
+        }
+        value.write(self.id, uniform.location);
+        Ok(())
+    }
+
+    /// Points a uniform block at a binding point, e.g. UniformBuffer::binding().
+    pub fn bind_uniform_block(&mut self, name: &str, binding: GLuint) -> Result<(), UniformError> {
+        let block = find_block(&mut self.reflection.uniform_blocks, name)?;
+        sgl::UniformBlockBinding(self.id, block.index, binding);
+        block.binding = binding;
+        Ok(())
+    }
+
+    /// Points a shader storage block at a binding point, e.g. StorageBuffer::binding().
+    pub fn bind_storage_block(&mut self, name: &str, binding: GLuint) -> Result<(), UniformError> {
+        let block = find_block(&mut self.reflection.storage_blocks, name)?;
+        sgl::ShaderStorageBlockBinding(self.id, block.index, binding);
+        block.binding = binding;
+        Ok(())
+    }
+
+    /// Sets a uniform, logging rather than failing if it can't be. For uniforms set every frame,
+    /// which require() checked when the program was loaded.
+    pub fn set_or_log<T: UniformValue>(&self, name: &str, value: T) {
+        if let Err(error) = self.set(name, value) {
+            log::error!("{}", error);
+        }
+    }
+
+    /// Checks the program has the uniforms, with their GL types, and the uniform or storage blocks
+    /// the code sets, so an edited shader that no longer matches can be refused when it's loaded.
+    pub fn require(&self, uniforms: &[(&str, GLenum)], blocks: &[&str]) -> Result<(), UniformError> {
+        for &(name, kind) in uniforms {
+            let uniform = self.reflection.uniform(name)
+                .ok_or_else(|| UniformError::NotFound(name.to_string()))?;
+            if uniform.kind != kind {
+                return Err(UniformError::WrongType { name: name.to_string(), expected: type_name(uniform.kind),
+                    found: type_name(kind) });
+            }
+        }
+        for &name in blocks {
+            let mut all_blocks = self.reflection.uniform_blocks.iter().chain(&self.reflection.storage_blocks);
+            if !all_blocks.any(|block| block.name == name) {
+                return Err(UniformError::BlockNotFound(name.to_string()));
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Drop for Program {
     fn drop(&mut self) {
         sgl::DeleteProgram(self.id);
     }
-}
\ No newline at end of file
+}
+
+fn check_linked(program_id: GLuint) -> Result<(), String> {
+    let mut success: GLint = 1;
+    sgl::GetProgramiv(program_id, gl::LINK_STATUS, &mut success);
+
+    if success == 0 {
+        let mut len: gl::types::GLint = 0;
+        sgl::GetProgramiv(program_id, gl::INFO_LOG_LENGTH, &mut len);
+
+        let error = create_whitespace_cstring_with_len(len as usize);
+        sgl::GetProgramInfoLog(
+            program_id,
+            len,
+            std::ptr::null_mut(),
+            error.as_ptr() as * mut gl::types::GLchar,
+        );
+
+
+        return Err(error.to_string_lossy().into_owned());
+    }
+    Ok(())
+}
+
+fn find_block<'a>(blocks: &'a mut [BlockInfo], name: &str) -> Result<&'a mut BlockInfo, UniformError> {
+    blocks.iter_mut().find(|block| block.name == name).ok_or_else(|| UniformError::BlockNotFound(name.to_string()))
+}
+
+fn resource_count(program_id: GLuint, interface: GLenum) -> GLuint {
+    let mut count: GLint = 0;
+    sgl::GetProgramInterfaceiv(program_id, interface, gl::ACTIVE_RESOURCES, &mut count);
+    count as GLuint
+}
+
+fn resource_properties<const N: usize>(program_id: GLuint, interface: GLenum, index: GLuint,
+                                        properties: [GLenum; N]) -> [GLint; N] {
+    let mut values = [0; N];
+    sgl::GetProgramResourceiv(program_id, interface, index, &properties, &mut values);
+    values
+}
+
+fn resource_name(program_id: GLuint, interface: GLenum, index: GLuint) -> String {
+    let [length] = resource_properties(program_id, interface, index, [gl::NAME_LENGTH]);
+    let mut buffer = vec![0; length.max(1) as usize];
+    let written = sgl::GetProgramResourceName(program_id, interface, index, &mut buffer);
+    buffer.truncate(written as usize);  // Without the NUL
+    String::from_utf8_lossy(&buffer).into_owned()
+}
+
+/// The GLSL name of a GL type, for error messages.
+pub fn type_name(kind: GLenum) -> &'static str {
+    match kind {
+        gl::FLOAT => "float",
+        gl::FLOAT_VEC2 => "vec2",
+        gl::FLOAT_VEC3 => "vec3",
+        gl::FLOAT_VEC4 => "vec4",
+        gl::INT => "int",
+        gl::INT_VEC2 => "ivec2",
+        gl::INT_VEC3 => "ivec3",
+        gl::INT_VEC4 => "ivec4",
+        gl::UNSIGNED_INT => "uint",
+        gl::UNSIGNED_INT_VEC2 => "uvec2",
+        gl::BOOL => "bool",
+        gl::FLOAT_MAT4 => "mat4",
+        gl::SAMPLER_2D => "sampler2D",
+        gl::SAMPLER_2D_ARRAY => "sampler2DArray",
+        gl::IMAGE_2D => "image2D",
+        gl::IMAGE_2D_ARRAY => "image2DArray",
+        _ => "unsupported type",
+    }
+}
+
+fn is_sampler(kind: GLenum) -> bool {
+    matches!(kind, gl::SAMPLER_1D..=gl::SAMPLER_2D_SHADOW | gl::SAMPLER_1D_ARRAY..=gl::SAMPLER_CUBE_SHADOW
+        | gl::INT_SAMPLER_1D..=gl::UNSIGNED_INT_SAMPLER_2D_ARRAY)
+}
+
+fn is_image(kind: GLenum) -> bool {
+    matches!(kind, gl::IMAGE_1D..=gl::UNSIGNED_INT_IMAGE_2D_MULTISAMPLE_ARRAY)
+}
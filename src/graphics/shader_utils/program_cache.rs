@@ -0,0 +1,113 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use gl::types::GLenum;
+
+use crate::graphics::sgl;
+use crate::graphics::shader_utils::hot_reload;
+use crate::graphics::shader_utils::program::Program;
+
+const CACHE_DIR_NAME: &str = "hort_mc/shaders";
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Whether linked programs are saved to and loaded from the cache. Off by default.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Dev mode rebuilds shaders on every edit, which would only fill the cache with stale binaries.
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed) && !hot_reload::dev_mode()
+}
+
+/// Where the platform keeps caches, with our own directory on the end. None if there's no home
+/// directory to put it in.
+pub fn cache_dir() -> Option<PathBuf> {
+    let base = if cfg!(target_os = "windows") {
+        env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Caches"))
+    } else {
+        env::var_os("XDG_CACHE_HOME").filter(|path| !path.is_empty()).map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+    };
+    base.map(|base| base.join(CACHE_DIR_NAME))
+}
+
+/// Identifies a program built from sources on the current driver. Binaries only load on the
+/// driver that made them, so its strings are part of the key as well as the sources.
+pub fn cache_key(sources: &[&str]) -> u64 {
+    let driver = [gl::VENDOR, gl::RENDERER, gl::VERSION].map(sgl::GetString);
+    let mut hash = FNV_OFFSET;
+    for part in sources.iter().copied().chain(driver.iter().map(String::as_str)) {
+        hash = fnv1a(hash, part.as_bytes());
+        hash = fnv1a(hash, &[0]);  // So moving text between parts changes the key
+    }
+    hash
+}
+
+/// Loads the program for the key, or builds it and saves its binary for next time. Anything
+/// wrong with the cache just means building from source.
+pub fn load_or_build<E>(name: &str, key: u64, build: impl FnOnce() -> Result<Program, E>)
+                        -> Result<Program, E> {
+    let path = enabled().then(cache_dir).flatten()
+        .map(|directory| directory.join(format!("{}-{:016x}.bin", name, key)));
+    let Some(path) = path else { return build() };
+
+    if let Ok(contents) = fs::read(&path) {
+        match load(&contents) {
+            Some(program) => return Ok(program),
+            None => { let _ = fs::remove_file(&path); }  // From an older driver, or corrupt
+        }
+    }
+
+    let program = build()?;
+    if let Some((format, binary)) = program.binary() {
+        let mut contents = format.to_le_bytes().to_vec();
+        contents.extend(binary);
+        let saved = path.parent().map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&path, contents));
+        match saved {
+            Ok(()) => remove_older_binaries(name, &path),
+            Err(error) => println!("Failed to save {} to the shader cache: {}", name, error),
+        }
+    }
+    Ok(program)
+}
+
+/// Anything else saved for the program was built from an older source or driver, so would never
+/// be loaded again.
+fn remove_older_binaries(name: &str, path: &Path) {
+    let Some(entries) = path.parent().and_then(|directory| fs::read_dir(directory).ok()) else { return };
+    let prefix = format!("{}-", name);
+    for entry in entries.flatten() {
+        let file_name = entry.file_name();
+        let Some(key) = file_name.to_str()
+            .and_then(|file_name| file_name.strip_prefix(&prefix))
+            .and_then(|rest| rest.strip_suffix(".bin")) else { continue };
+        if key.len() == 16 && key.chars().all(|c| c.is_ascii_hexdigit()) && entry.path() != path {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// A cache file is the binary's format as 4 little endian bytes, then the binary.
+fn load(contents: &[u8]) -> Option<Program> {
+    let (format, binary) = contents.split_first_chunk::<4>()?;
+    Program::from_binary(GLenum::from_le_bytes(*format), binary).ok()
+}
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// FNV-1a, as std's hashers aren't guaranteed to give the same hash between Rust versions.
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
@@ -0,0 +1,128 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+
+use image::RgbaImage;
+
+use crate::arguments::Arguments;
+use crate::graphics::headless::{render_frame, HeadlessContext, STILL_ADAPTATION_TIME};
+use crate::graphics::post_processing::post_processor::PostProcessor;
+use crate::graphics::world_renderer::camera::Camera;
+use crate::graphics::world_renderer::camera_path::CameraPath;
+use crate::graphics::world_renderer::ray_tracer::RayTracer;
+use crate::world::World;
+
+const ENCODER: &str = "ffmpeg";
+
+/// Where exported frames go.
+enum FrameSink {
+    Sequence(PathBuf),  // Directory of numbered PNGs
+    Encoder { process: Child, stdin: ChildStdin },  // ffmpeg reading raw RGBA frames
+}
+
+impl FrameSink {
+    fn sequence(directory: &Path) -> Result<FrameSink, String> {
+        fs::create_dir_all(directory)
+            .map_err(|error| format!("Failed to create {}: {}", directory.display(), error))?;
+        Ok(FrameSink::Sequence(directory.to_path_buf()))
+    }
+
+    fn encoder(output: &Path, width: u32, height: u32, fps: f32) -> Result<FrameSink, String> {
+        let mut process = Command::new(ENCODER)
+            .args(["-y", "-loglevel", "error", "-f", "rawvideo", "-pixel_format", "rgba"])
+            .args(["-video_size", &format!("{}x{}", width, height)])
+            .args(["-framerate", &fps.to_string(), "-i", "-", "-pix_fmt", "yuv420p"])
+            .arg(output)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|error| format!("Failed to start {} ({}), use --frames to export a PNG \
+                                      sequence instead", ENCODER, error))?;
+        let stdin = process.stdin.take().unwrap();
+        Ok(FrameSink::Encoder { process, stdin })
+    }
+
+    fn write(&mut self, frame: u32, image: &RgbaImage) -> Result<(), String> {
+        match self {
+            FrameSink::Sequence(directory) => {
+                let path = directory.join(format!("frame_{:05}.png", frame));
+                image.save(&path)
+                    .map_err(|error| format!("Failed to save {}: {}", path.display(), error))
+            }
+            FrameSink::Encoder { stdin, .. } => stdin.write_all(image.as_raw())
+                .map_err(|error| format!("Failed to send frame {} to {}: {}", frame, ENCODER, error)),
+        }
+    }
+
+    fn finish(self) -> Result<(), String> {
+        if let FrameSink::Encoder { mut process, stdin } = self {
+            drop(stdin);  // Closing stdin tells the encoder there are no more frames
+            let status = process.wait().map_err(|error| error.to_string())?;
+            if !status.success() {
+                return Err(format!("{} failed with {}", ENCODER, status));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Renders the camera path at a fixed frame rate into --frames as a PNG sequence or --video
+/// through ffmpeg. Each frame advances simulated time by exactly 1 / fps however long it takes
+/// to render, so the video plays back at the right speed.
+pub fn export_camera_path(arguments: &Arguments) -> Result<(), String> {
+    if arguments.width == 0 || arguments.height == 0 {
+        return Err(String::from("The video needs a width and height of at least 1"));
+    }
+    if !(arguments.fps.is_finite() && arguments.fps > 0.0) {
+        return Err(String::from("The frame rate must be a number above 0"));
+    }
+    let path = CameraPath::load(&arguments.camera_path)?;
+    if path.is_empty() {
+        return Err(format!("{} has no keyframes", arguments.camera_path.display()));
+    }
+
+    let encoding = arguments.frames.is_none() && arguments.video.is_some();
+    if encoding && (!arguments.width.is_multiple_of(2) || !arguments.height.is_multiple_of(2)) {
+        // yuv420p stores colour at half resolution, so ffmpeg refuses odd sizes
+        return Err(format!("Videos need an even width and height, but the size is {}x{}", arguments.width,
+                           arguments.height));
+    }
+
+    let context = HeadlessContext::new();
+    let missing = context.capabilities().missing_for_gpu_renderer();
+    if !missing.is_empty() {
+        return Err(format!("Exporting needs the GPU renderer, which needs {}", missing.join(" and ")));
+    }
+
+    let mut sink = match (&arguments.frames, &arguments.video) {
+        (Some(directory), _) => FrameSink::sequence(directory)?,
+        (None, Some(output)) => FrameSink::encoder(output, arguments.width, arguments.height,
+                                                   arguments.fps)?,
+        (None, None) => return Err(String::from("No --frames directory or --video file given")),
+    };
+    let rendered = render_frames(arguments, &path, &mut sink);
+    // Wait for the encoder even after an error, so it isn't left behind and can say what it got
+    let finished = sink.finish();
+    rendered.and(finished)
+}
+
+/// Renders every frame into the sink, leaving finishing it to the caller.
+fn render_frames(arguments: &Arguments, path: &CameraPath, sink: &mut FrameSink) -> Result<(), String> {
+    let mut ray_tracer = RayTracer::new(Camera::default(), World::demo(), Default::default());
+    let mut post_processor = PostProcessor::new(Default::default());
+
+    let frame_time = 1.0 / arguments.fps;
+    let frame_count = (path.duration() * arguments.fps).floor() as u32 + 1;
+    for frame in 0..frame_count {
+        if let Some(keyframe) = path.sample(frame as f32 * frame_time) {
+            keyframe.apply(&mut ray_tracer.camera);
+        }
+        // Let exposure settle on the first frame rather than fading in from middle grey
+        let delta_time = if frame == 0 { STILL_ADAPTATION_TIME } else { frame_time };
+        let image = render_frame(&mut ray_tracer, &mut post_processor,
+                                 (arguments.width, arguments.height), arguments.samples, delta_time);
+        sink.write(frame, &image.ldr)?;
+        println!("Frame {}/{}", frame + 1, frame_count);
+    }
+    Ok(())
+}
@@ -1,16 +1,83 @@
-use sdl2::Sdl;
+use std::collections::HashMap;
+use std::env;
+use std::io;
+
+use sdl2::{Sdl, VideoSubsystem};
 use sdl2::video;
-use sdl2::video::GLContext;
+use sdl2::video::{FullscreenType, GLContext};
 
+use crate::config;
+use crate::graphics::capabilities::{GlCapabilities, GPU_RENDERER_VERSION};
 use crate::graphics::error_handler::setup_error_handler;
 
+const GEOMETRY_FILE: &str = "window.cfg";
+const FALLBACK_VERSION: (u8, u8) = (3, 3);  // Enough to show the cpu renderer's output
+
+/// Position and size of the window when it isn't fullscreen, remembered between runs.
+#[derive(Debug, Clone, Copy)]
+pub struct WindowGeometry {
+    pub x: Option<i32>,  // None to let the window manager place us
+    pub y: Option<i32>,
+    pub width: u32,
+    pub height: u32,
+    pub fullscreen: FullscreenType,
+}
+
+impl WindowGeometry {
+    /// The remembered geometry, falling back to the given size for anything not saved.
+    pub fn load(default_width: u32, default_height: u32) -> WindowGeometry {
+        let values = config::load(GEOMETRY_FILE).unwrap_or_else(|error| {
+            if error.kind() != io::ErrorKind::NotFound {
+                println!("Failed to read window geometry: {}", error);
+            }
+            HashMap::new()
+        });
+        let get = |key: &str| values.get(key).and_then(|value| value.parse().ok());
+
+        let fullscreen = match values.get("fullscreen").map(String::as_str) {
+            Some("desktop") => FullscreenType::Desktop,
+            Some("exclusive") => FullscreenType::True,
+            _ => FullscreenType::Off,
+        };
+
+        WindowGeometry {
+            x: get("x"),
+            y: get("y"),
+            width: get("width").map_or(default_width, |width: i32| width.max(1) as u32),
+            height: get("height").map_or(default_height, |height: i32| height.max(1) as u32),
+            fullscreen,
+        }
+    }
+
+    pub fn save(&self) {
+        let fullscreen = match self.fullscreen {
+            FullscreenType::Off => "off",
+            FullscreenType::Desktop => "desktop",
+            FullscreenType::True => "exclusive",
+        };
+        let mut values = vec![("width", self.width.to_string()),
+                              ("height", self.height.to_string()),
+                              ("fullscreen", fullscreen.to_string())];
+        if let (Some(x), Some(y)) = (self.x, self.y) {
+            values.push(("x", x.to_string()));
+            values.push(("y", y.to_string()));
+        }
+
+        if let Err(error) = config::save(GEOMETRY_FILE, &values) {
+            println!("Failed to save window geometry: {}", error);
+        }
+    }
+}
+
 pub struct Window {
     pub name: &'static str,
-    pub width: u32,
+    pub width: u32,  // Drawable size, in pixels
     pub height: u32,
     pub sdl: Sdl,
     pub window: video::Window,
     pub gl_context: GLContext,
+    pub capabilities: GlCapabilities,
+    windowed_geometry: WindowGeometry,  // What to go back to when leaving fullscreen
 }
 
 impl Window {
@@ -18,27 +85,114 @@ impl Window {
         let sdl = sdl2::init().unwrap();
         let video_subsystem = sdl.video().unwrap();
 
-        let gl_attr = video_subsystem.gl_attr();
+        let geometry = WindowGeometry::load(width, height);
+        let mut builder = video_subsystem.window(name, geometry.width, geometry.height);
+        builder.opengl().resizable();
+        match (geometry.x, geometry.y) {
+            (Some(x), Some(y)) => builder.position(x, y),
+            _ => builder.position_centered(),
+        };
+        let mut window = builder.build().unwrap();
+        let (gl_context, capabilities) = Window::create_gl_context(&video_subsystem, &window);
 
+        if geometry.fullscreen != FullscreenType::Off {
+            window.set_fullscreen(geometry.fullscreen).err();
+        }
 
-        gl_attr.set_context_profile(video::GLProfile::Core);
-        gl_attr.set_context_version(4, 1);
+        let (width, height) = window.drawable_size();
+        Window { name, width, height, sdl, window, gl_context, capabilities,
+            windowed_geometry: WindowGeometry { fullscreen: FullscreenType::Off, ..geometry } }
+    }
 
-        let window = video_subsystem
-            .window(name, width, height)
-            .opengl()
-            .resizable()
-            .build()
-            .unwrap();
+    /// A hidden window, just for its GL context when rendering offscreen. Without a display
+    /// (e.g. on CI) SDL's offscreen driver is used, which gets a surfaceless EGL context so this
+    /// works with Mesa's llvmpipe on machines without a GPU.
+    pub fn new_hidden(name: &'static str) -> Window {
+        let has_display = env::var_os("DISPLAY").is_some() || env::var_os("WAYLAND_DISPLAY").is_some();
+        if !has_display && env::var_os("SDL_VIDEODRIVER").is_none() {
+            sdl2::hint::set("SDL_VIDEODRIVER", "offscreen");
+        }
 
-        let gl_context = window.gl_create_context().unwrap();
+        let sdl = sdl2::init().unwrap();
+        let video_subsystem = sdl.video().unwrap();
+
+        let window = video_subsystem.window(name, 1, 1).opengl().hidden().build().unwrap();
+        let (gl_context, capabilities) = Window::create_gl_context(&video_subsystem, &window);
+
+        let windowed_geometry = WindowGeometry { x: None, y: None, width: 1, height: 1,
+            fullscreen: FullscreenType::Off };
+        Window { name, width: 1, height: 1, sdl, window, gl_context, capabilities, windowed_geometry }
+    }
+
+    fn set_gl_attributes(video_subsystem: &VideoSubsystem, (major, minor): (u8, u8)) {
+        let gl_attr = video_subsystem.gl_attr();
+        gl_attr.set_context_profile(video::GLProfile::Core);
+        gl_attr.set_context_version(major, minor);
+        gl_attr.set_context_flags().forward_compatible().set();  // macOS only gives core contexts with this
+    }
+
+    /// Creates the context and loads the gl functions for it. Asks for the version the gpu
+    /// renderer needs, falling back to an older one the cpu renderer can use if the driver
+    /// doesn't have it, so check the capabilities before using anything newer.
+    fn create_gl_context(video_subsystem: &VideoSubsystem, window: &video::Window)
+                         -> (GLContext, GlCapabilities) {
+        let gpu_version = (GPU_RENDERER_VERSION.0 as u8, GPU_RENDERER_VERSION.1 as u8);
+        Window::set_gl_attributes(video_subsystem, gpu_version);
+        let gl_context = window.gl_create_context().or_else(|error| {
+            log::warn!("No OpenGL {}.{} context ({}), trying {}.{}", gpu_version.0, gpu_version.1, error,
+                       FALLBACK_VERSION.0, FALLBACK_VERSION.1);
+            Window::set_gl_attributes(video_subsystem, FALLBACK_VERSION);
+            window.gl_create_context()
+        }).unwrap_or_else(|error| panic!("Failed to create an OpenGL context: {}", error));
         gl::load_with(|s| video_subsystem.gl_get_proc_address(s)
             as *const std::os::raw::c_void);
 
-        setup_error_handler();
+        let capabilities = GlCapabilities::detect();
+        if capabilities.debug_output() {
+            setup_error_handler();
+        }
 
         video_subsystem.gl_set_swap_interval(0).err();
+        (gl_context, capabilities)
+    }
 
-        Window { name, width, height, sdl, window, gl_context }
+    /// Re-reads the drawable size after the window was resized.
+    pub fn update_size(&mut self) -> (u32, u32) {
+        let (width, height) = self.window.drawable_size();
+        self.width = width.max(1);
+        self.height = height.max(1);
+        return (self.width, self.height);
     }
-}
\ No newline at end of file
+
+    /// Switches between windowed and the given fullscreen type, Desktop being borderless.
+    pub fn toggle_fullscreen(&mut self, fullscreen: FullscreenType) {
+        let current = self.window.fullscreen_state();
+        if current == FullscreenType::Off {
+            self.windowed_geometry = self.current_geometry();
+        }
+
+        let new = if current == fullscreen { FullscreenType::Off } else { fullscreen };
+        if let Err(error) = self.window.set_fullscreen(new) {
+            println!("Failed to change fullscreen mode: {}", error);
+        }
+    }
+
+    fn current_geometry(&self) -> WindowGeometry {
+        let (x, y) = self.window.position();
+        let (width, height) = self.window.size();
+        WindowGeometry { x: Some(x), y: Some(y), width, height,
+            fullscreen: self.window.fullscreen_state() }
+    }
+
+    /// Remembers where the window is for next time. When fullscreen, the size it had before
+    /// going fullscreen is saved along with the fullscreen mode.
+    pub fn save_geometry(&self) {
+        let fullscreen = self.window.fullscreen_state();
+        let geometry = if fullscreen == FullscreenType::Off {
+            self.current_geometry()
+        } else {
+            WindowGeometry { fullscreen, ..self.windowed_geometry }
+        };
+        geometry.save();
+    }
+}
@@ -1,104 +1,269 @@
-use std::ffi::c_void;
-use std::mem::size_of;
-use std::ptr;
-
-use gl::types::{GLsizeiptr, GLuint};
-use std140::{mat3x3, vec2, vec3};
-
-use crate::graphics::sgl;
-
-fn make_yaw_pitch_roll_matrix(yaw: f32, pitch: f32, roll: f32) -> mat3x3 {
-    let yaw = yaw.to_radians();
-    let pitch = pitch.to_radians();
-    let roll = roll.to_radians();
-
-    let cos_yaw = yaw.cos();
-    let sin_yaw = yaw.sin();
-    let cos_pitch = pitch.cos();
-    let sin_pitch = pitch.sin();
-    let cos_roll = roll.cos();
-    let sin_roll = roll.sin();
-
-    let rotation_matrix = mat3x3(
-        vec3(cos_yaw * cos_roll + sin_yaw * sin_pitch * sin_roll,
-             sin_roll * cos_pitch,
-             -sin_yaw * cos_roll + cos_yaw * sin_pitch * sin_roll),
-        vec3(-cos_yaw * sin_roll + sin_yaw * sin_pitch * cos_roll,
-             cos_roll * cos_pitch,
-             sin_yaw * sin_roll + cos_yaw * sin_pitch * cos_roll
-        ),
-        vec3(
-            sin_yaw * cos_pitch,
-            -sin_pitch,
-            cos_yaw * cos_pitch
-        )
-    );
-
-    return rotation_matrix;
+use gl::types::GLuint;
+use std140::{float, int, mat4x4};
+
+use crate::graphics::shader_buffers::UniformBuffer;
+use crate::maths::matrix::Mat4;
+use crate::maths::quaternion::Quaternion;
+use crate::maths::vector::Vec3;
+
+const MAX_PITCH: f32 = 89.9;  // Degrees, stops the view flipping over when looking straight up
+
+/// How rays are spread out from the camera. Angles are in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    Perspective { vertical_fov: f32 },
+    Equirectangular,  // Full 360 degree panorama
+    Fisheye { fov: f32 },  // Equidistant, fov is across the image circle and may go past 180
+    Orthographic { height: f32 },  // Height of the view in blocks, e.g. for top down maps
+}
+
+impl Projection {
+    /// The next projection type with default settings, for cycling through them at runtime.
+    pub fn next(self) -> Projection {
+        match self {
+            Projection::Perspective { .. } => Projection::Equirectangular,
+            Projection::Equirectangular => Projection::Fisheye { fov: 180.0 },
+            Projection::Fisheye { .. } => Projection::Orthographic { height: 16.0 },
+            Projection::Orthographic { .. } => Projection::Perspective { vertical_fov: 90.0 },
+        }
+    }
+
+    fn shader_id(self) -> i32 {  // Matches the PROJECTION_ defines in ray_tracer.comp
+        match self {
+            Projection::Perspective { .. } => 0,
+            Projection::Equirectangular => 1,
+            Projection::Fisheye { .. } => 2,
+            Projection::Orthographic { .. } => 3,
+        }
+    }
+
+    pub fn fov(self) -> f32 {
+        match self {
+            Projection::Perspective { vertical_fov } => vertical_fov,
+            Projection::Fisheye { fov } => fov,
+            _ => 0.0,
+        }
+    }
+
+    /// The same projection with a different fov, unchanged for projections without one.
+    pub fn with_fov(self, fov: f32) -> Projection {
+        match self {
+            Projection::Perspective { .. } => Projection::Perspective { vertical_fov: fov },
+            Projection::Fisheye { .. } => Projection::Fisheye { fov },
+            projection => projection,
+        }
+    }
+
+    pub fn orthographic_height(self) -> f32 {
+        match self {
+            Projection::Orthographic { height } => height,
+            _ => 0.0,
+        }
+    }
 }
 
 #[std140::repr_std140]
 #[derive(Debug)]
 struct CameraBuffer { // All values are duplicates of Camera
-pos: vec3,
-    rot: mat3x3,
-    fov: vec2
+    view: mat4x4,
+    inverse_view: mat4x4,
+    projection: int,
+    fov: float,
+    orthographic_height: float,
+    aspect_ratio: float
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Camera {
-    pos: vec3, // Y is up
-    pitch: f32,
-    yaw: f32,  // 0,0,0 Would be looking towards positive Z
-    roll: f32,
-    fov: vec2,
-
-    buffer_id: GLuint,
-    buffer: CameraBuffer
+    position: Vec3, // Y is up
+    orientation: Quaternion,  // Identity would be looking towards positive Z
+    projection: Projection,
+    aspect_ratio: f32,  // Width / height of the image
+    moved: bool,  // Since the last update
 }
 
 impl Camera {
-    pub fn new(pos: vec3, pitch: f32, yaw: f32, roll: f32, fov: vec2) -> Camera {
-        let mut buffer_id: GLuint = 0;
-
-        sgl::GenBuffers(1, &mut buffer_id);
-        sgl::BindBuffer(gl::UNIFORM_BUFFER, buffer_id);
-        sgl::BufferData(gl::UNIFORM_BUFFER, size_of::<CameraBuffer>() as GLsizeiptr, ptr::null(),
-                       gl::DYNAMIC_DRAW);
-        sgl::BindBufferBase(gl::UNIFORM_BUFFER, 0, buffer_id);
-
-        let camera = Camera { pos, pitch, yaw, roll, fov, buffer_id, buffer: CameraBuffer {
-            pos, rot: make_yaw_pitch_roll_matrix(yaw, pitch, roll), fov}};
+    /// Angles are in degrees, positive pitch looks down.
+    pub fn new(position: Vec3, pitch: f32, yaw: f32, roll: f32, projection: Projection) -> Camera {
+        let pitch = pitch.clamp(-MAX_PITCH, MAX_PITCH);
+        let orientation = Quaternion::from_yaw_pitch_roll(yaw.to_radians(), pitch.to_radians(),
+                                                          roll.to_radians());
 
-        return camera;
+        return Camera { position, orientation, projection, aspect_ratio: 1.0, moved: true };
     }
 
+    /// Yaw turns around the world's up axis, pitch and roll around the camera's own axes. Pitch is
+    /// clamped so the camera can't go past looking straight up or down.
     pub fn look_rel(&mut self, rel_yaw: f32, rel_pitch: f32, rel_roll: f32) {
-        //println!("rot {} {} {}", self.yaw, self.pitch, self.roll);
-        self.yaw += rel_yaw;
-        self.pitch += rel_pitch;
-        self.roll += rel_roll;
+        let pitch = self.pitch();
+        let rel_pitch = (pitch + rel_pitch).clamp(-MAX_PITCH, MAX_PITCH) - pitch;
+
+        self.orientation = Quaternion::from_axis_angle(Vec3::Y, rel_yaw.to_radians())
+            * self.orientation
+            * Quaternion::from_axis_angle(Vec3::X, rel_pitch.to_radians())
+            * Quaternion::from_axis_angle(Vec3::Z, rel_roll.to_radians());
+        self.orientation = self.orientation.normalize();
+        self.moved = true;
     }
 
     pub fn move_rel(&mut self, rel_x: f32, rel_y: f32, rel_z: f32) {
-        //println!("pos {} {} {}", self.pos.0, self.pos.1, self.pos.2);
-        self.pos.0 += rel_x;
-        self.pos.1 += rel_y;
-        self.pos.2 += rel_z;
+        self.position += Vec3::new(rel_x, rel_y, rel_z);
+        self.moved = true;
+    }
+
+    pub fn position(&self) -> Vec3 {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: Vec3) {
+        self.position = position;
+        self.moved = true;
+    }
+
+    pub fn orientation(&self) -> Quaternion {
+        self.orientation
+    }
+
+    pub fn set_orientation(&mut self, orientation: Quaternion) {
+        self.orientation = orientation.normalize();
+        self.moved = true;
+    }
+
+    /// Degrees above (negative) or below (positive) the horizon the camera is looking.
+    pub fn pitch(&self) -> f32 {
+        (-self.forward().y).clamp(-1.0, 1.0).asin().to_degrees()
+    }
+
+    pub fn forward(&self) -> Vec3 {
+        self.orientation.rotate(Vec3::Z)
+    }
+
+    pub fn right(&self) -> Vec3 {
+        self.orientation.rotate(Vec3::X)
     }
 
-    pub fn update(&mut self) {  // Update data on the gpu
-        self.buffer.pos = self.pos;
-        self.buffer.fov = self.fov;
-        self.buffer.rot = make_yaw_pitch_roll_matrix(self.yaw, self.pitch, self.roll);
-        sgl::BindBuffer(gl::UNIFORM_BUFFER, self.buffer_id);
-        sgl::BufferSubData(gl::UNIFORM_BUFFER, 0, size_of::<CameraBuffer>() as GLsizeiptr,
-                           ptr::addr_of!(self.buffer) as *const CameraBuffer as *const c_void);
+    pub fn up(&self) -> Vec3 {
+        self.orientation.rotate(Vec3::Y)
+    }
+
+    /// Transforms from camera space, where the camera looks down +Z, to world space.
+    pub fn inverse_view_matrix(&self) -> Mat4 {
+        Mat4::from_columns(self.right(), self.up(), self.forward(), self.position)
+    }
+
+    /// Transforms from world space to camera space.
+    pub fn view_matrix(&self) -> Mat4 {
+        self.inverse_view_matrix().rigid_inverse()
+    }
+
+    /// Origin and direction of the ray through a point on the image, matching make_ray in
+    /// ray_tracer.comp. ndc goes from -1 to 1 across the image with y up. Returns None where the
+    /// projection has no ray, e.g. outside a fisheye's image circle.
+    pub fn ray(&self, ndc_x: f32, ndc_y: f32) -> Option<(Vec3, Vec3)> {
+        let mut origin = Vec3::ZERO;
+        let direction = match self.projection {
+            Projection::Perspective { vertical_fov } => {
+                let tan_half_fov = (vertical_fov.to_radians() / 2.0).tan();
+                Vec3::new(ndc_x * self.aspect_ratio * tan_half_fov, ndc_y * tan_half_fov, 1.0)
+            }
+            Projection::Equirectangular => {
+                let longitude = ndc_x * std::f32::consts::PI;
+                let latitude = ndc_y * std::f32::consts::FRAC_PI_2;
+                Vec3::new(longitude.sin() * latitude.cos(), latitude.sin(),
+                          longitude.cos() * latitude.cos())
+            }
+            Projection::Fisheye { fov } => {
+                let (x, y) = (ndc_x * self.aspect_ratio, ndc_y);
+                let radius = (x * x + y * y).sqrt();
+                let theta = radius * fov.to_radians() / 2.0;
+                if radius > 1.0 || theta > std::f32::consts::PI {
+                    return None;
+                }
+                let (side_x, side_y) = if radius > 0.0 { (x / radius, y / radius) } else { (0.0, 0.0) };
+                Vec3::new(side_x * theta.sin(), side_y * theta.sin(), theta.cos())
+            }
+            Projection::Orthographic { height } => {
+                origin = Vec3::new(ndc_x * self.aspect_ratio, ndc_y, 0.0) * (height / 2.0);
+                Vec3::Z
+            }
+        };
+
+        let inverse_view = self.inverse_view_matrix();
+        Some((inverse_view.transform_point(origin),
+              inverse_view.transform_vector(direction).normalize()))
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+        self.moved = true;
+    }
+
+    pub fn aspect_ratio(&self) -> f32 {
+        self.aspect_ratio
+    }
+
+    pub fn set_aspect_ratio(&mut self, width: u32, height: u32) {
+        self.aspect_ratio = width as f32 / height.max(1) as f32;
+        self.moved = true;
+    }
+
+    /// Returns whether the camera has moved since this was last called.
+    pub fn take_moved(&mut self) -> bool {
+        let moved = self.moved;
+        self.moved = false;
+        return moved;
     }
 }
 
 impl Default for Camera {
     fn default() -> Camera {
-        return Camera::new(vec3(0.0, 0.0, 0.0), 0.0, 0.0, 0.0, vec2(90.0, 90.0))
+        return Camera::new(Vec3::ZERO, 0.0, 0.0, 0.0,
+                           Projection::Perspective { vertical_fov: 90.0 })
     }
-}
\ No newline at end of file
+}
+
+/// The camera's data on the gpu, for CameraBlock in ray_tracer.comp. Rewritten every frame, so
+/// it's streamed.
+#[derive(Debug)]
+pub struct CameraUniform {
+    buffer: UniformBuffer<CameraBuffer>,
+}
+
+impl CameraUniform {
+    pub fn new() -> CameraUniform {
+        CameraUniform::default()
+    }
+
+    fn data(camera: &Camera) -> CameraBuffer {
+        CameraBuffer {
+            view: camera.view_matrix().into(),
+            inverse_view: camera.inverse_view_matrix().into(),
+            projection: int(camera.projection.shader_id()),
+            fov: float(camera.projection.fov()),
+            orthographic_height: float(camera.projection.orthographic_height()),
+            aspect_ratio: float(camera.aspect_ratio),
+        }
+    }
+
+    pub fn binding(&self) -> GLuint {
+        self.buffer.binding()
+    }
+
+    /// Update data on the gpu.
+    pub fn update(&mut self, camera: &Camera) {
+        self.buffer.set(CameraUniform::data(camera));
+        self.buffer.flush();
+    }
+}
+
+impl Default for CameraUniform {
+    fn default() -> CameraUniform {
+        let buffer = UniformBuffer::streamed(CameraUniform::data(&Camera::default()));
+        buffer.set_label("Camera");
+        return CameraUniform { buffer };
+    }
+}
@@ -0,0 +1,193 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::graphics::world_renderer::camera::Camera;
+use crate::maths::quaternion::Quaternion;
+use crate::maths::vector::Vec3;
+
+const RECORD_INTERVAL: f32 = 0.5;  // Seconds between keyframes when recording
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraKeyframe {
+    pub time: f32,  // Seconds from the start of the path
+    pub position: Vec3,
+    pub orientation: Quaternion,
+    pub fov: f32,  // Degrees, ignored by projections without a fov
+}
+
+impl CameraKeyframe {
+    pub fn from_camera(camera: &Camera, time: f32) -> CameraKeyframe {
+        CameraKeyframe { time, position: camera.position(), orientation: camera.orientation(),
+            fov: camera.projection().fov() }
+    }
+
+    /// Moves the camera to this keyframe.
+    pub fn apply(&self, camera: &mut Camera) {
+        camera.set_position(self.position);
+        camera.set_orientation(self.orientation);
+        camera.set_projection(camera.projection().with_fov(self.fov));
+    }
+}
+
+/// A sequence of keyframes the camera can be moved along, for reproducible flythroughs. Positions
+/// and fov are interpolated with Catmull-Rom splines, orientations with slerp.
+///
+/// In files each keyframe is a line of `time x y z yaw pitch roll fov`, with angles in degrees.
+/// Blank lines and lines starting with # are ignored.
+#[derive(Debug, Clone, Default)]
+pub struct CameraPath {
+    keyframes: Vec<CameraKeyframe>,  // Sorted by time
+}
+
+impl CameraPath {
+    pub fn new(mut keyframes: Vec<CameraKeyframe>) -> CameraPath {
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        CameraPath { keyframes }
+    }
+
+    pub fn load(path: &Path) -> Result<CameraPath, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|error| format!("Failed to read {}: {}", path.display(), error))?;
+        CameraPath::parse(&contents)
+            .map_err(|error| format!("{} in {}", error, path.display()))
+    }
+
+    pub fn parse(contents: &str) -> Result<CameraPath, String> {
+        let mut keyframes = Vec::new();
+        for (line_number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let values: Vec<f32> = line.split_whitespace()
+                .map(|value| value.parse::<f32>())
+                .collect::<Result<_, _>>()
+                .map_err(|error| format!("{} on line {}", error, line_number + 1))?;
+            if values.len() != 8 {
+                return Err(format!("Expected 8 values but found {} on line {}", values.len(),
+                                   line_number + 1));
+            }
+
+            let orientation = Quaternion::from_yaw_pitch_roll(
+                values[4].to_radians(), values[5].to_radians(), values[6].to_radians());
+            keyframes.push(CameraKeyframe { time: values[0],
+                position: Vec3::new(values[1], values[2], values[3]), orientation,
+                fov: values[7] });
+        }
+        Ok(CameraPath::new(keyframes))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut contents = String::from("# time x y z yaw pitch roll fov\n");
+        for keyframe in &self.keyframes {
+            let (yaw, pitch, roll) = keyframe.orientation.to_yaw_pitch_roll();
+            contents += &format!("{} {} {} {} {} {} {} {}\n", keyframe.time, keyframe.position.x,
+                                 keyframe.position.y, keyframe.position.z, yaw.to_degrees(),
+                                 pitch.to_degrees(), roll.to_degrees(), keyframe.fov);
+        }
+        fs::write(path, contents)
+    }
+
+    pub fn keyframes(&self) -> &[CameraKeyframe] {
+        &self.keyframes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keyframes.is_empty()
+    }
+
+    /// Time of the last keyframe.
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    /// Adds a keyframe, keeping them sorted by time.
+    pub fn push(&mut self, keyframe: CameraKeyframe) {
+        let index = self.keyframes.partition_point(|other| other.time <= keyframe.time);
+        self.keyframes.insert(index, keyframe);
+    }
+
+    /// The interpolated camera at a time, clamped to the ends of the path. None if there are no
+    /// keyframes.
+    pub fn sample(&self, time: f32) -> Option<CameraKeyframe> {
+        let keyframes = &self.keyframes;
+        let first = keyframes.first()?;
+        let last = keyframes.last()?;
+        if time <= first.time {
+            return Some(CameraKeyframe { time, ..*first });
+        }
+        if time >= last.time {
+            return Some(CameraKeyframe { time, ..*last });
+        }
+
+        let next = keyframes.partition_point(|keyframe| keyframe.time <= time);
+        let previous = next - 1;
+        let (start, end) = (&keyframes[previous], &keyframes[next]);
+        let span = end.time - start.time;
+        let t = if span > 0.0 { (time - start.time) / span } else { 1.0 };
+
+        let position = hermite(start.position, end.position,
+                               self.tangent(previous, |k| k.position) * span,
+                               self.tangent(next, |k| k.position) * span, t);
+        let fov = hermite(Vec3::new(start.fov, 0.0, 0.0), Vec3::new(end.fov, 0.0, 0.0),
+                          self.tangent(previous, |k| Vec3::new(k.fov, 0.0, 0.0)) * span,
+                          self.tangent(next, |k| Vec3::new(k.fov, 0.0, 0.0)) * span, t).x;
+
+        Some(CameraKeyframe { time, position, orientation: start.orientation.slerp(end.orientation, t),
+            fov })
+    }
+
+    /// Catmull-Rom tangent at a keyframe, per second so it works with uneven keyframe spacing.
+    fn tangent(&self, index: usize, value: impl Fn(&CameraKeyframe) -> Vec3) -> Vec3 {
+        let before = &self.keyframes[index.saturating_sub(1)];
+        let after = &self.keyframes[(index + 1).min(self.keyframes.len() - 1)];
+        let span = after.time - before.time;
+        if span <= 0.0 {
+            return Vec3::ZERO;
+        }
+        (value(after) - value(before)) / span
+    }
+}
+
+/// Cubic hermite interpolation between start and end with the given tangents.
+fn hermite(start: Vec3, end: Vec3, start_tangent: Vec3, end_tangent: Vec3, t: f32) -> Vec3 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    start * (2.0 * t3 - 3.0 * t2 + 1.0) + start_tangent * (t3 - 2.0 * t2 + t)
+        + end * (-2.0 * t3 + 3.0 * t2) + end_tangent * (t3 - t2)
+}
+
+/// Records the camera into a path at regular intervals while flying around.
+#[derive(Debug, Default)]
+pub struct CameraPathRecorder {
+    path: CameraPath,
+    elapsed: f32,
+    since_last_keyframe: f32,
+}
+
+impl CameraPathRecorder {
+    pub fn new(camera: &Camera) -> CameraPathRecorder {
+        let mut recorder = CameraPathRecorder::default();
+        recorder.path.push(CameraKeyframe::from_camera(camera, 0.0));
+        recorder
+    }
+
+    pub fn update(&mut self, camera: &Camera, delta_time: f32) {
+        self.elapsed += delta_time;
+        self.since_last_keyframe += delta_time;
+        if self.since_last_keyframe >= RECORD_INTERVAL {
+            self.since_last_keyframe = 0.0;
+            self.path.push(CameraKeyframe::from_camera(camera, self.elapsed));
+        }
+    }
+
+    /// Stops recording, adding a final keyframe where the camera is now.
+    pub fn finish(mut self, camera: &Camera) -> CameraPath {
+        if self.since_last_keyframe > 0.0 {
+            self.path.push(CameraKeyframe::from_camera(camera, self.elapsed));
+        }
+        self.path
+    }
+}
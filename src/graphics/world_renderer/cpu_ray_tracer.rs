@@ -0,0 +1,237 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use image::{Rgba, Rgba32FImage};
+
+use crate::graphics::screen_copier::upscaler::halton;
+use crate::graphics::world_renderer::camera::Camera;
+use crate::graphics::world_renderer::material::{Material, MATERIALS};
+use crate::maths::vector::Vec3;
+use crate::world::World;
+
+const EPSILON: f32 = 0.0001;
+const MAX_STEPS: usize = 10;
+const MAX_COLLISIONS: usize = 10;  // RayTracerSettings::max_bounces by default
+const AMBIENT_COLOR: Vec3 = Vec3 { x: 0.7, y: 0.7, z: 1.0 };
+
+#[derive(Debug, Clone)]
+pub struct CpuRayTracerSettings {
+    pub tile_size: (u32, u32),  // Each thread takes a tile at a time
+    pub threads: Option<usize>,  // None to use every core
+    pub seed: u32,  // Same as RayTracerSettings::seed, so both give the same noise
+}
+
+impl Default for CpuRayTracerSettings {
+    fn default() -> CpuRayTracerSettings {
+        CpuRayTracerSettings { tile_size: (32, 32), threads: None, seed: 2 }
+    }
+}
+
+/// The same traversal and shading as ray_tracer.comp, on the cpu. It's a reference for what the
+/// shader should output, a fallback where compute shaders aren't available, and needs no GL
+/// context so it can be used in tests.
+///
+/// Keep this in step with the shader, including its quirks, or the two won't match.
+#[derive(Debug, Clone)]
+pub struct CpuRayTracer {
+    pub camera: Camera,
+    pub settings: CpuRayTracerSettings,
+}
+
+impl CpuRayTracer {
+    pub fn new(camera: Camera, settings: CpuRayTracerSettings) -> CpuRayTracer {
+        CpuRayTracer { camera, settings }
+    }
+
+    /// Renders an image with samples averaged per pixel, jittered the same way as accumulating
+    /// on the gpu. The first row of the image is the top, as with textures read back from GL.
+    pub fn render(&self, world: &World, width: u32, height: u32, samples: u32) -> Rgba32FImage {
+        let mut camera = self.camera.clone();
+        camera.set_aspect_ratio(width, height);
+
+        let (tile_width, tile_height) = self.settings.tile_size;
+        let (tile_width, tile_height) = (tile_width.clamp(1, width.max(1)),
+                                         tile_height.clamp(1, height.max(1)));
+        let tiles_x = width.div_ceil(tile_width);
+        let tile_count = tiles_x * height.div_ceil(tile_height);
+        let threads = self.settings.threads
+            .unwrap_or_else(|| thread::available_parallelism().map_or(1, |threads| threads.get()))
+            .clamp(1, tile_count.max(1) as usize);
+
+        let image = Mutex::new(Rgba32FImage::new(width, height));
+        let next_tile = AtomicU32::new(0);
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| loop {
+                    let tile = next_tile.fetch_add(1, Ordering::Relaxed);
+                    if tile >= tile_count {
+                        break;
+                    }
+                    let x = (tile % tiles_x) * tile_width;
+                    let y = (tile / tiles_x) * tile_height;
+                    let this_tile_width = tile_width.min(width - x);
+                    let this_tile_height = tile_height.min(height - y);
+
+                    let mut pixels = Vec::with_capacity((this_tile_width * this_tile_height) as usize);
+                    for pixel_y in y..y + this_tile_height {
+                        for pixel_x in x..x + this_tile_width {
+                            let color = self.render_pixel(&camera, world, (pixel_x, pixel_y),
+                                                          (width, height), samples);
+                            pixels.push((pixel_x, pixel_y, color));
+                        }
+                    }
+
+                    let mut image = image.lock().unwrap();
+                    for (pixel_x, pixel_y, color) in pixels {
+                        image.put_pixel(pixel_x, height - 1 - pixel_y,
+                                        Rgba([color.x, color.y, color.z, 1.0]));
+                    }
+                });
+            }
+        });
+        image.into_inner().unwrap()
+    }
+
+    /// Averages every sample for a pixel. Positions are in GL's convention, with y up.
+    fn render_pixel(&self, camera: &Camera, world: &World, pixel: (u32, u32), image_size: (u32, u32),
+                    samples: u32) -> Vec3 {
+        let mut color = Vec3::ZERO;
+        for sample in 0..samples.max(1) {
+            let jitter = if samples > 1 {
+                (halton(sample + 1, 2) - 0.5, halton(sample + 1, 3) - 0.5)
+            } else {
+                (0.0, 0.0)
+            };
+            let sample_color = trace_sample(camera, world, pixel, image_size, jitter,
+                                            self.settings.seed.wrapping_add(sample))
+                .unwrap_or(Vec3::ZERO);
+            color = color.lerp(sample_color, 1.0 / (sample + 1) as f32);  // Running average
+        }
+        color
+    }
+}
+
+/// One sample of a pixel, as one invocation of ray_tracer.comp with the seed and sample_index
+/// uniforms added together. None where the camera has no ray, which the shader colours black.
+pub(crate) fn trace_sample(camera: &Camera, world: &World, (x, y): (u32, u32), (width, height): (u32, u32),
+                           jitter: (f32, f32), seed: u32) -> Option<Vec3> {
+    let ndc_x = (x as f32 + 0.5 + jitter.0) / width as f32 * 2.0 - 1.0;
+    let ndc_y = (y as f32 + 0.5 + jitter.1) / height as f32 * 2.0 - 1.0;
+
+    let pixel_index = y.wrapping_mul(width).wrapping_add(x);
+    let rng_state = pixel_index.wrapping_add(seed.wrapping_mul(719393));
+    let (position, direction) = camera.ray(ndc_x, ndc_y)?;
+    Some(trace(world, Ray { position, direction }, rng_state))
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Ray {
+    position: Vec3,
+    direction: Vec3,
+}
+
+fn trace(world: &World, mut ray: Ray, rng_state: u32) -> Vec3 {
+    let mut collision_materials = [Material::default(); MAX_COLLISIONS];
+    let mut collisions = 0;
+    for _ in 0..MAX_STEPS {
+        ray = step_block(ray);
+        let block_id = block_collision(world, ray);
+
+        if block_id != 0 {  // Ignore air
+            collision_materials[collisions] = MATERIALS.get(block_id as usize).copied()
+                .unwrap_or_default();
+            collisions += 1;
+            if collisions >= MAX_COLLISIONS {
+                break;
+            }
+            let material = collision_materials[collisions - 1];  // The block just hit
+            let is_specular_bounce = material.specular_probability >= random_value(rng_state);
+
+            let face = face_mask(ray.position);
+            let diffuse_direction = (-face + random_direction(rng_state)).normalize();
+            let specular_direction = reflect(ray.direction, -face);
+            let blend = material.smoothness * if is_specular_bounce { 1.0 } else { 0.0 };
+            ray.direction = diffuse_direction.lerp(specular_direction, blend).normalize();
+        }
+    }
+
+    if collisions == 0 {
+        return AMBIENT_COLOR;
+    }
+    let mut color = Vec3::ZERO;  // We wait till hit light source
+    for material in collision_materials[..collisions].iter().rev() {
+        color = color.scale(material.color) + material.color * material.emission;
+    }
+    color
+}
+
+fn fract(value: f32) -> f32 {
+    value - value.floor()
+}
+
+/// 1 for each axis the position is on a block boundary of.
+fn face_mask(position: Vec3) -> Vec3 {
+    let on_boundary = |value: f32| if fract(value) == 0.0 { 1.0 } else { 0.0 };
+    Vec3::new(on_boundary(position.x), on_boundary(position.y), on_boundary(position.z))
+}
+
+fn has_integer(position: Vec3) -> bool {
+    face_mask(position) != Vec3::ZERO
+}
+
+fn step_block(mut ray: Ray) -> Ray {
+    ray.position += ray.direction * EPSILON;
+    let mut loops = 0;
+    while !has_integer(ray.position) && loops < 3 {  // Protection to stop infinite looping
+        loops += 1;
+        let time_to = |position: f32, direction: f32| if direction > 0.0 {
+            (position.ceil() - position) / direction
+        } else {
+            (position - position.floor()) / direction.abs()
+        };
+        let time = time_to(ray.position.x, ray.direction.x)
+            .min(time_to(ray.position.y, ray.direction.y))
+            .min(time_to(ray.position.z, ray.direction.z));
+        ray.position += ray.direction * time;
+    }
+    ray
+}
+
+/// Block the ray is entering, where it's on a boundary the block on the side it's heading to.
+fn block_collision(world: &World, ray: Ray) -> u32 {
+    let face = face_mask(ray.position);
+    let heading_back = |direction: f32| if direction < 0.0 { 1.0 } else { 0.0 };
+    let position = ray.position.floor() - face.scale(Vec3::new(heading_back(ray.direction.x),
+                                                               heading_back(ray.direction.y),
+                                                               heading_back(ray.direction.z)));
+    world.block_at(position.x as i32, position.y as i32, position.z as i32)
+}
+
+fn reflect(direction: Vec3, normal: Vec3) -> Vec3 {
+    direction - normal * (2.0 * normal.dot(direction))
+}
+
+fn next_random(state: u32) -> u32 {
+    let state = state.wrapping_mul(747796405).wrapping_add(2891336453);
+    let result = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277803737);
+    (result >> 22) ^ result
+}
+
+fn random_value(state: u32) -> f32 {
+    next_random(state) as f32 / 4294967295.0  // 2^32 - 1
+}
+
+#[allow(clippy::approx_constant)]  // The shader's value of pi, so the noise matches exactly
+fn random_value_normal_distribution(state: u32) -> f32 {
+    let theta = 2.0 * 3.1415926 * random_value(state);
+    let rho = (-2.0 * random_value(state).ln()).sqrt();
+    rho * theta.cos()
+}
+
+fn random_direction(state: u32) -> Vec3 {
+    let x = random_value_normal_distribution(state);
+    let y = random_value_normal_distribution(state);
+    let z = random_value_normal_distribution(state);
+    Vec3::new(x, y, z).normalize()
+}
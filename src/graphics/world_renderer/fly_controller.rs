@@ -0,0 +1,73 @@
+use crate::graphics::world_renderer::camera::Camera;
+use crate::input::action::Action;
+use crate::input::state::InputState;
+use crate::maths::vector::Vec3;
+
+const STOPPED_SPEED: f32 = 0.001;  // Below this we snap to a stop, so the camera counts as still
+
+/// Free flying camera movement relative to where the camera is facing, with mouse look.
+#[derive(Debug, Clone)]
+pub struct FlyController {
+    pub speed: f32,  // Blocks per second
+    pub sprint_multiplier: f32,
+    pub slow_multiplier: f32,
+    pub acceleration: f32,  // How quickly we get up to speed, higher is snappier
+    pub damping: f32,  // How quickly we stop once keys are released
+    pub mouse_sensitivity: f32,  // Degrees per pixel of mouse movement
+    pub look_speed: f32,  // Degrees per second at full stick, for controllers
+
+    velocity: Vec3,
+}
+
+impl FlyController {
+    pub fn new(speed: f32, mouse_sensitivity: f32) -> FlyController {
+        FlyController { speed, sprint_multiplier: 4.0, slow_multiplier: 0.25, acceleration: 10.0,
+            damping: 8.0, mouse_sensitivity, look_speed: 120.0, velocity: Vec3::ZERO }
+    }
+
+    /// Moves the camera for this frame from the held movement and look actions, plus the mouse.
+    pub fn update(&mut self, camera: &mut Camera, input: &InputState, delta_time: f32) {
+        let mouse_delta = input.mouse_delta();
+        let look_yaw = mouse_delta.0 as f32 * self.mouse_sensitivity
+            + input.axis(Action::LookLeft, Action::LookRight) * self.look_speed * delta_time;
+        let look_pitch = mouse_delta.1 as f32 * self.mouse_sensitivity
+            + input.axis(Action::LookUp, Action::LookDown) * self.look_speed * delta_time;
+        if look_yaw != 0.0 || look_pitch != 0.0 {
+            camera.look_rel(look_yaw, look_pitch, 0.0);
+        }
+
+        let forward = input.axis(Action::MoveBackward, Action::MoveForward);
+        let right = input.axis(Action::MoveLeft, Action::MoveRight);
+        let up = input.axis(Action::MoveDown, Action::MoveUp);
+
+        let direction = camera.forward() * forward + camera.right() * right + Vec3::Y * up;
+        let direction = if direction.length() > 1.0 { direction.normalize() } else { direction };
+        let mut speed = self.speed;
+        speed *= 1.0 + (self.sprint_multiplier - 1.0) * input.value(Action::Sprint);
+        speed *= 1.0 + (self.slow_multiplier - 1.0) * input.value(Action::Slow);
+
+        let has_input = direction != Vec3::ZERO;
+        let rate = if has_input { self.acceleration } else { self.damping };
+        let blend = 1.0 - (-rate * delta_time).exp();  // Frame rate independent smoothing
+        self.velocity = self.velocity.lerp(direction * speed, blend);
+
+        if !has_input && self.velocity.length() < STOPPED_SPEED {
+            self.velocity = Vec3::ZERO;
+        }
+        if self.velocity != Vec3::ZERO {
+            let offset = self.velocity * delta_time;
+            camera.move_rel(offset.x, offset.y, offset.z);
+        }
+    }
+
+    /// Stop immediately, e.g. when the camera is moved by something else.
+    pub fn stop(&mut self) {
+        self.velocity = Vec3::ZERO;
+    }
+}
+
+impl Default for FlyController {
+    fn default() -> FlyController {
+        FlyController::new(5.0, 0.15)
+    }
+}
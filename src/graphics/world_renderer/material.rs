@@ -0,0 +1,43 @@
+use crate::graphics::shader_buffers::Std430;
+use crate::maths::vector::Vec3;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Material {
+    pub color: Vec3,  // Should be 1 or bellow as doesn't emit light
+    pub emission: f32,  // This multiplied by color makes light emission
+    pub specular_probability: f32,
+    pub smoothness: f32,
+}
+
+/// Every block's material, indexed by block id.
+pub const MATERIALS: [Material; 4] = [
+    Material { color: Vec3::ZERO, emission: 0.0, specular_probability: 0.0, smoothness: 0.0 },  // Air is ignored
+    Material { color: Vec3 { x: 0.2, y: 0.2, z: 0.2 }, emission: 0.0, specular_probability: 0.0,
+        smoothness: 0.0 },  // Stone
+    Material { color: Vec3 { x: 0.0, y: 1.0, z: 0.0 }, emission: 0.0, specular_probability: 1.0,
+        smoothness: 1.0 },  // Grass
+    Material { color: Vec3 { x: 1.0, y: 1.0, z: 0.7 }, emission: 1.0, specular_probability: 0.0,
+        smoothness: 0.0 },  // Light
+];
+
+/// A Material laid out like Material in ray_tracer.comp's MaterialBlock.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct MaterialData {
+    color: [f32; 3],
+    emission: f32,
+    specular_probability: f32,
+    smoothness: f32,
+    _padding: [f32; 2],  // The vec3 makes the struct 16 byte aligned
+}
+
+// Only f32s, in the order and with the padding std430 gives the GLSL struct
+unsafe impl Std430 for MaterialData {}
+
+impl From<Material> for MaterialData {
+    fn from(material: Material) -> MaterialData {
+        MaterialData { color: [material.color.x, material.color.y, material.color.z],
+            emission: material.emission, specular_probability: material.specular_probability,
+            smoothness: material.smoothness, _padding: [0.0; 2] }
+    }
+}
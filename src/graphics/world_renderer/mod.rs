@@ -1,2 +1,7 @@
 pub mod camera;
-pub mod ray_tracer;
\ No newline at end of file
+pub mod camera_path;
+pub mod cpu_ray_tracer;
+pub mod fly_controller;
+pub mod material;
+pub mod ray_tracer;
+pub mod world_buffers;
\ No newline at end of file
@@ -1,45 +1,152 @@
-use std::ffi::CString;
-
-use gl::types::GLuint;
-
-use crate::graphics::{sgl, update_texture_binding_point};
+use crate::graphics::{sgl, DebugGroup, Texture2D};
+use crate::graphics::backend::{GlBackend, Rect, RenderBackend, TraceParams};
+use crate::graphics::shader_utils::{compute_program, require_interface};
+use crate::graphics::shader_utils::error::ShaderError;
+use crate::graphics::shader_utils::hot_reload::shader_file;
 use crate::graphics::shader_utils::program::Program;
-use crate::graphics::shader_utils::shader::Shader;
-use crate::graphics::world_renderer::camera::Camera;
+use crate::graphics::world_renderer::camera::{Camera, CameraUniform};
+use crate::graphics::world_renderer::world_buffers::WorldBuffers;
 use crate::world::World;
 
-#[allow(dead_code)]
-pub struct RayTracer {
+#[derive(Debug, Clone)]
+pub struct RayTracerSettings {
+    pub workgroup_size: (u32, u32),  // Baked into the shader, so only read when it's compiled
+    pub tile_size: Option<(u32, u32)>,  // Split the image into separately submitted dispatches
+    pub tiles_per_frame: Option<u32>,  // Spread tiles over multiple frames, needs a tile_size
+    pub seed: u32,  // For the random numbers, rendering with the same seed gives the same image
+    pub max_bounces: u32,  // Blocks a ray can hit before giving up, baked into the shader
+}
+
+impl Default for RayTracerSettings {
+    fn default() -> RayTracerSettings {
+        RayTracerSettings { workgroup_size: (8, 8), tile_size: None, tiles_per_frame: None,
+            seed: 2, max_bounces: 10 }
+    }
+}
+
+pub struct RayTracer<B: RenderBackend = GlBackend> {
     pub(crate) camera: Camera,
+    pub(crate) jitter: (f32, f32),  // Sub pixel offset of each ray, in pixels
+    pub(crate) sample_index: u32,  // Samples already in the texture to average with, 0 replaces them
+    pub settings: RayTracerSettings,
     world: World,
-
-    shader_program: Program
+    camera_moved: bool,
+    next_tile: u32,  // For progressive rendering, where we got to in the image
+    backend: B,
+    kernel: B::RayTraceKernel,
 }
 
 impl RayTracer {
-    pub fn new(camera: Camera, world: World) -> RayTracer {
-        let shader_program = RayTracer::load_shaders();
+    pub fn new(camera: Camera, world: World, settings: RayTracerSettings) -> RayTracer {
+        RayTracer::with_backend(GlBackend, camera, world, settings)
+    }
+}
+
+impl<B: RenderBackend> RayTracer<B> {
+    pub fn with_backend(backend: B, camera: Camera, world: World, settings: RayTracerSettings) -> RayTracer<B> {
+        let kernel = backend.load_ray_tracer(&settings, &world).unwrap_or_else(|error| panic!("{}", error));
+        RayTracer {camera, jitter: (0.0, 0.0), sample_index: 0, settings, world, camera_moved: true,
+            next_tile: 0, backend, kernel}
+    }
 
-        RayTracer {camera, world, shader_program}
+    /// Recompiles the shader, e.g. after it was edited or the workgroup size changed. The old
+    /// shader is kept if the new one fails to compile.
+    pub fn reload_shaders(&mut self) -> Result<(), ShaderError> {
+        self.kernel = self.backend.load_ray_tracer(&self.settings, &self.world)?;
+        Ok(())
     }
 
-    fn load_shaders() -> Program {
-        let comp_shader =
-            Shader::from_comp_source(&CString::new(include_str!("ray_tracer.comp")).unwrap())
-                .unwrap();
+    /// Traces the image into texture. With progressive rendering only some of the tiles are
+    /// traced, so this returns whether the image was finished.
+    pub fn render_to(&mut self, texture: &B::Texture) -> bool {
+        let (width, height) = self.backend.texture_size(texture);
+        self.camera_moved = self.camera.take_moved();
 
-        let shader_program = Program::from_shaders(
-            &[comp_shader]).unwrap();
-        return shader_program;
+        let (tile_width, tile_height) = self.settings.tile_size.unwrap_or((width, height));
+        let tile_width = tile_width.clamp(1, width.max(1));
+        let tile_height = tile_height.clamp(1, height.max(1));
+        let tiles_x = width.div_ceil(tile_width);
+        let tile_count = tiles_x * height.div_ceil(tile_height);
+
+        if self.camera_moved || self.next_tile >= tile_count {
+            self.next_tile = 0;
+        }
+        let last_tile = match self.settings.tiles_per_frame {
+            Some(tiles_per_frame) => (self.next_tile + tiles_per_frame.max(1)).min(tile_count),
+            None => tile_count,
+        };
+
+        let tiles: Vec<Rect> = (self.next_tile..last_tile).map(|tile| {
+            let x = (tile % tiles_x) * tile_width;
+            let y = (tile / tiles_x) * tile_height;
+            Rect::new(x, y, tile_width.min(width - x), tile_height.min(height - y))
+        }).collect();
+        let params = TraceParams { camera: &self.camera, world: &self.world, jitter: self.jitter,
+            sample_index: self.sample_index, seed: self.settings.seed };
+        self.backend.dispatch_ray_trace(&mut self.kernel, texture, &params, &tiles);
+
+        self.next_tile = last_tile;
+        return last_tile == tile_count;
+    }
+
+    /// Whether the camera moved before the last render, meaning anything accumulated over
+    /// previous frames is out of date.
+    pub fn camera_moved(&self) -> bool {
+        self.camera_moved
+    }
+}
+
+/// ray_tracer.comp and the buffers it reads, for GlBackend.
+pub struct GlRayTraceKernel {
+    shader_program: Program,
+    workgroup_size: (u32, u32),
+    camera_uniform: CameraUniform,
+    world_buffers: WorldBuffers,
+}
+
+impl GlRayTraceKernel {
+    pub(crate) fn load(settings: &RayTracerSettings, world: &World) -> Result<GlRayTraceKernel, ShaderError> {
+        let file = shader_file!("ray_tracer.comp");
+        let shader_program = compute_program(file, &[
+            ("WORKGROUP_SIZE_X", settings.workgroup_size.0.to_string()),
+            ("WORKGROUP_SIZE_Y", settings.workgroup_size.1.to_string()),
+            ("MAX_BOUNCES", settings.max_bounces.max(1).to_string()),
+        ])?;
+        let shader_program = require_interface(shader_program, file, &[
+            ("jitter", gl::FLOAT_VEC2), ("image_size", gl::INT_VEC2), ("sample_index", gl::INT), ("seed", gl::INT),
+            ("tile_offset", gl::INT_VEC2),
+        ], &["CameraBlock", "WorldBlock", "WorldBlocks", "MaterialBlock"])?;
+        Ok(GlRayTraceKernel { shader_program, workgroup_size: settings.workgroup_size,
+            camera_uniform: CameraUniform::new(), world_buffers: WorldBuffers::new(world) })
     }
 
-    pub fn render_to(&mut self, texture: GLuint, width: u32, height: u32) {
+    pub(crate) fn dispatch(&mut self, texture: &Texture2D, params: &TraceParams, tiles: &[Rect]) {
+        let (width, height) = texture.size();
+        let _group = DebugGroup::push("Ray tracing");
         self.shader_program.set_used();
 
-        update_texture_binding_point(texture, 0);
+        texture.bind_image(0, gl::READ_WRITE);
+
+        self.camera_uniform.update(params.camera);
+        if let Err(error) = self.shader_program.bind_uniform_block("CameraBlock", self.camera_uniform.binding()) {
+            log::error!("{}", error);
+        }
+        self.world_buffers.bind(&mut self.shader_program);
+        let program = &self.shader_program;
+        program.set_or_log("jitter", params.jitter);
+        program.set_or_log("image_size", (width as i32, height as i32));
+        program.set_or_log("sample_index", params.sample_index as i32);
+        program.set_or_log("seed", params.seed as i32);
 
-        self.camera.update();
-        sgl::DispatchCompute(width, height, 1);
-        sgl::MemoryBarrier(gl::SHADER_IMAGE_ACCESS_BARRIER_BIT);
+        let (workgroup_width, workgroup_height) = self.workgroup_size;
+        for tile in tiles {
+            program.set_or_log("tile_offset", (tile.x as i32, tile.y as i32));
+            sgl::DispatchCompute(tile.width.div_ceil(workgroup_width), tile.height.div_ceil(workgroup_height), 1);
+            if (tile.width, tile.height) != (width, height) {
+                sgl::Flush();  // Submit each tile on its own so no single submission is too long
+            }
+        }
+        // Post processing samples the image with texture() as well as loading it as an image
+        sgl::MemoryBarrier(gl::SHADER_IMAGE_ACCESS_BARRIER_BIT | gl::TEXTURE_FETCH_BARRIER_BIT);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,73 @@
+use std140::ivec3;
+
+use crate::graphics::shader_buffers::{StorageBuffer, UniformBuffer};
+use crate::graphics::shader_utils::program::Program;
+use crate::graphics::world_renderer::material::{MaterialData, MATERIALS};
+use crate::world::World;
+
+#[std140::repr_std140]
+#[derive(Debug)]
+struct WorldInfoBuffer {  // Matches WorldBlock in ray_tracer.comp
+    start: ivec3,
+    size: ivec3,
+}
+
+/// The world and block materials on the gpu for ray_tracer.comp. Blocks are stored densely over
+/// the world's bounds, x major then y then z.
+#[derive(Debug)]
+pub struct WorldBuffers {
+    info: UniformBuffer<WorldInfoBuffer>,
+    blocks: StorageBuffer<u32>,
+    materials: StorageBuffer<MaterialData>,
+}
+
+impl WorldBuffers {
+    pub fn new(world: &World) -> WorldBuffers {
+        let (info, blocks) = WorldBuffers::world_data(world);
+        let materials = MATERIALS.iter().map(|material| MaterialData::from(*material)).collect();
+        let world_buffers = WorldBuffers { info: UniformBuffer::new(info), blocks: StorageBuffer::new(blocks),
+            materials: StorageBuffer::new(materials) };
+        world_buffers.info.set_label("World info");
+        world_buffers.blocks.set_label("World blocks");
+        world_buffers.materials.set_label("Materials");
+        world_buffers
+    }
+
+    fn world_data(world: &World) -> (WorldInfoBuffer, Vec<u32>) {
+        let Some((start, size)) = world.bounds() else {
+            // Storage buffers can't be empty, so keep one block for nothing to index
+            return (WorldInfoBuffer { start: ivec3(0, 0, 0), size: ivec3(0, 0, 0) }, vec![0]);
+        };
+        let mut blocks = Vec::with_capacity((size.0 * size.1 * size.2) as usize);
+        for x in 0..size.0 {
+            for y in 0..size.1 {
+                for z in 0..size.2 {
+                    blocks.push(world.block_at(start.0 + x, start.1 + y, start.2 + z));
+                }
+            }
+        }
+        (WorldInfoBuffer { start: ivec3(start.0, start.1, start.2), size: ivec3(size.0, size.1, size.2) },
+         blocks)
+    }
+
+    /// Uploads a changed world, replacing the last one.
+    pub fn set_world(&mut self, world: &World) {
+        let (info, blocks) = WorldBuffers::world_data(world);
+        self.info.set(info);
+        self.blocks.replace(blocks);
+    }
+
+    /// Uploads anything that changed and points the program's blocks at the buffers. Needs
+    /// doing again for a reloaded program.
+    pub fn bind(&mut self, program: &mut Program) {
+        self.info.flush();
+        self.blocks.flush();
+        self.materials.flush();
+        let bound = program.bind_uniform_block("WorldBlock", self.info.binding())
+            .and_then(|_| program.bind_storage_block("WorldBlocks", self.blocks.binding()))
+            .and_then(|_| program.bind_storage_block("MaterialBlock", self.materials.binding()));
+        if let Err(error) = bound {
+            log::error!("{}", error);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+/// Something the player can do, which physical inputs are bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveForward,
+    MoveBackward,
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    Sprint,
+    Slow,
+    LookLeft,  // Look actions are for analog sticks, the mouse is handled separately
+    LookRight,
+    LookUp,
+    LookDown,
+    CaptureMouse,
+    ReleaseMouse,
+    ToggleFullscreen,
+    ToggleExclusiveFullscreen,
+    CycleToneMapper,
+    ToggleBloom,
+    ToggleAutoExposure,
+    ToggleSrgb,
+    ExposureUp,
+    ExposureDown,
+    CycleUpscaleFilter,
+    RenderScaleUp,
+    RenderScaleDown,
+    CycleProjection,
+    RecordCameraPath,
+    PlayCameraPath,
+    Screenshot,
+    HighResStill,
+    ToggleProfiler,
+    ExportProfile,
+}
+
+impl Action {
+    pub const ALL: [Action; 32] = [
+        Action::MoveForward, Action::MoveBackward, Action::MoveLeft, Action::MoveRight,
+        Action::MoveUp, Action::MoveDown, Action::Sprint, Action::Slow,
+        Action::LookLeft, Action::LookRight, Action::LookUp, Action::LookDown,
+        Action::CaptureMouse, Action::ReleaseMouse,
+        Action::ToggleFullscreen, Action::ToggleExclusiveFullscreen,
+        Action::CycleToneMapper, Action::ToggleBloom, Action::ToggleAutoExposure,
+        Action::ToggleSrgb, Action::ExposureUp, Action::ExposureDown,
+        Action::CycleUpscaleFilter, Action::RenderScaleUp, Action::RenderScaleDown,
+        Action::CycleProjection, Action::RecordCameraPath, Action::PlayCameraPath,
+        Action::Screenshot, Action::HighResStill, Action::ToggleProfiler, Action::ExportProfile,
+    ];
+
+    /// Name used in the bindings config file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::MoveForward => "move_forward",
+            Action::MoveBackward => "move_backward",
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::MoveUp => "move_up",
+            Action::MoveDown => "move_down",
+            Action::Sprint => "sprint",
+            Action::Slow => "slow",
+            Action::LookLeft => "look_left",
+            Action::LookRight => "look_right",
+            Action::LookUp => "look_up",
+            Action::LookDown => "look_down",
+            Action::CaptureMouse => "capture_mouse",
+            Action::ReleaseMouse => "release_mouse",
+            Action::ToggleFullscreen => "toggle_fullscreen",
+            Action::ToggleExclusiveFullscreen => "toggle_exclusive_fullscreen",
+            Action::CycleToneMapper => "cycle_tone_mapper",
+            Action::ToggleBloom => "toggle_bloom",
+            Action::ToggleAutoExposure => "toggle_auto_exposure",
+            Action::ToggleSrgb => "toggle_srgb",
+            Action::ExposureUp => "exposure_up",
+            Action::ExposureDown => "exposure_down",
+            Action::CycleUpscaleFilter => "cycle_upscale_filter",
+            Action::RenderScaleUp => "render_scale_up",
+            Action::RenderScaleDown => "render_scale_down",
+            Action::CycleProjection => "cycle_projection",
+            Action::RecordCameraPath => "record_camera_path",
+            Action::PlayCameraPath => "play_camera_path",
+            Action::Screenshot => "screenshot",
+            Action::HighResStill => "high_res_still",
+            Action::ToggleProfiler => "toggle_profiler",
+            Action::ExportProfile => "export_profile",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    /// Position in Action::ALL, for storing per action data in arrays.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
@@ -0,0 +1,259 @@
+use std::fmt;
+use std::io;
+
+use sdl2::controller::{Axis, Button};
+use sdl2::event::Event;
+use sdl2::keyboard::{KeyboardState, Mod, Scancode};
+use sdl2::mouse::MouseButton;
+
+use crate::config;
+use crate::input::action::Action;
+
+const BINDINGS_FILE: &str = "bindings.cfg";
+const SEPARATOR: char = '|';
+
+/// Modifier keys that must also be held for a key binding, either side of the keyboard counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct KeyModifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+}
+
+impl KeyModifiers {
+    pub fn held(&self, keyboard: &KeyboardState) -> bool {
+        let either = |left, right| keyboard.is_scancode_pressed(left)
+            || keyboard.is_scancode_pressed(right);
+        (!self.ctrl || either(Scancode::LCtrl, Scancode::RCtrl))
+            && (!self.alt || either(Scancode::LAlt, Scancode::RAlt))
+            && (!self.shift || either(Scancode::LShift, Scancode::RShift))
+    }
+
+    /// The same check against the modifiers SDL gives with a key event.
+    pub fn held_in(&self, keymod: Mod) -> bool {
+        (!self.ctrl || keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD))
+            && (!self.alt || keymod.intersects(Mod::LALTMOD | Mod::RALTMOD))
+            && (!self.shift || keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD))
+    }
+}
+
+/// A physical input. In the config file these are written as SDL's key names with optional
+/// `Ctrl+`, `Alt+` and `Shift+` prefixes, `Mouse:<button>`, `Pad:<button>` or `Pad:<axis>+/-`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputBinding {
+    Key { scancode: Scancode, modifiers: KeyModifiers },
+    MouseButton(MouseButton),
+    ControllerButton(Button),
+    ControllerAxis { axis: Axis, positive: bool },  // Which direction of the axis counts
+}
+
+impl InputBinding {
+    pub fn key(scancode: Scancode) -> InputBinding {
+        InputBinding::Key { scancode, modifiers: KeyModifiers::default() }
+    }
+
+    pub fn parse(text: &str) -> Result<InputBinding, String> {
+        let text = text.trim();
+
+        if let Some(button) = text.strip_prefix("Mouse:") {
+            let button = match button {
+                "Left" => MouseButton::Left,
+                "Middle" => MouseButton::Middle,
+                "Right" => MouseButton::Right,
+                "X1" => MouseButton::X1,
+                "X2" => MouseButton::X2,
+                _ => return Err(format!("Unknown mouse button \"{}\"", button)),
+            };
+            return Ok(InputBinding::MouseButton(button));
+        }
+
+        if let Some(input) = text.strip_prefix("Pad:") {
+            for (suffix, positive) in [('+', true), ('-', false)] {
+                if let Some(axis) = input.strip_suffix(suffix) {
+                    return match Axis::from_string(axis) {
+                        Some(axis) => Ok(InputBinding::ControllerAxis { axis, positive }),
+                        None => Err(format!("Unknown controller axis \"{}\"", axis)),
+                    };
+                }
+            }
+            return match Button::from_string(input) {
+                Some(button) => Ok(InputBinding::ControllerButton(button)),
+                None => Err(format!("Unknown controller button \"{}\"", input)),
+            };
+        }
+
+        let mut modifiers = KeyModifiers::default();
+        let mut key = text;
+        loop {
+            if let Some(rest) = key.strip_prefix("Ctrl+") {
+                modifiers.ctrl = true;
+                key = rest;
+            } else if let Some(rest) = key.strip_prefix("Alt+") {
+                modifiers.alt = true;
+                key = rest;
+            } else if let Some(rest) = key.strip_prefix("Shift+") {
+                modifiers.shift = true;
+                key = rest;
+            } else {
+                break;
+            }
+        }
+        match Scancode::from_name(key) {
+            Some(scancode) => Ok(InputBinding::Key { scancode, modifiers }),
+            None => Err(format!("Unknown key \"{}\"", key)),
+        }
+    }
+
+    /// Whether the event is this binding being pressed. Axes only have a polled value.
+    pub fn pressed_by(&self, event: &Event) -> bool {
+        match (*self, event) {
+            (InputBinding::Key { scancode, modifiers },
+             Event::KeyDown { scancode: Some(pressed), keymod, repeat: false, .. }) =>
+                scancode == *pressed && modifiers.held_in(*keymod),
+            (InputBinding::MouseButton(button), Event::MouseButtonDown { mouse_btn, .. }) => button == *mouse_btn,
+            (InputBinding::ControllerButton(button), Event::ControllerButtonDown { button: pressed, .. }) =>
+                button == *pressed,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for InputBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputBinding::Key { scancode, modifiers } => {
+                if modifiers.ctrl {
+                    write!(f, "Ctrl+")?;
+                }
+                if modifiers.alt {
+                    write!(f, "Alt+")?;
+                }
+                if modifiers.shift {
+                    write!(f, "Shift+")?;
+                }
+                write!(f, "{}", scancode.name())
+            }
+            InputBinding::MouseButton(button) => write!(f, "Mouse:{:?}", button),
+            InputBinding::ControllerButton(button) => write!(f, "Pad:{}", button.string()),
+            InputBinding::ControllerAxis { axis, positive } =>
+                write!(f, "Pad:{}{}", axis.string(), if *positive { '+' } else { '-' }),
+        }
+    }
+}
+
+/// Which physical inputs trigger each action.
+#[derive(Debug, Clone)]
+pub struct InputBindings {
+    bindings: Vec<Vec<InputBinding>>,  // Indexed by Action::index
+}
+
+impl InputBindings {
+    /// Loads bindings.cfg from the config directory. Actions it doesn't mention keep their
+    /// default bindings, and if there is no file the defaults are written out for editing.
+    pub fn load() -> InputBindings {
+        let mut bindings = InputBindings::default();
+        let values = match config::load(BINDINGS_FILE) {
+            Ok(values) => values,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                bindings.save();
+                return bindings;
+            }
+            Err(error) => {
+                println!("Failed to read {}, using the default bindings: {}", BINDINGS_FILE, error);
+                return bindings;
+            }
+        };
+
+        for (name, value) in values {
+            let action = match Action::from_name(&name) {
+                Some(action) => action,
+                None => {
+                    println!("Unknown action \"{}\" in {}", name, BINDINGS_FILE);
+                    continue;
+                }
+            };
+
+            let parsed = value.split(SEPARATOR)
+                .filter(|binding| !binding.trim().is_empty())
+                .filter_map(|binding| match InputBinding::parse(binding) {
+                    Ok(binding) => Some(binding),
+                    Err(error) => {
+                        println!("{} for {} in {}", error, name, BINDINGS_FILE);
+                        None
+                    }
+                })
+                .collect();
+            bindings.set(action, parsed);
+        }
+        return bindings;
+    }
+
+    pub fn save(&self) {
+        let values: Vec<(&str, String)> = Action::ALL.iter()
+            .map(|action| {
+                let bindings: Vec<String> = self.get(*action).iter()
+                    .map(|binding| binding.to_string())
+                    .collect();
+                (action.name(), bindings.join(&format!(" {} ", SEPARATOR)))
+            })
+            .collect();
+
+        if let Err(error) = config::save(BINDINGS_FILE, &values) {
+            println!("Failed to save input bindings: {}", error);
+        }
+    }
+
+    pub fn get(&self, action: Action) -> &[InputBinding] {
+        &self.bindings[action.index()]
+    }
+
+    pub fn set(&mut self, action: Action, bindings: Vec<InputBinding>) {
+        self.bindings[action.index()] = bindings;
+    }
+}
+
+impl Default for InputBindings {
+    fn default() -> InputBindings {
+        let key = InputBinding::key;
+        let pad_axis = |axis, positive| InputBinding::ControllerAxis { axis, positive };
+        let pad_button = InputBinding::ControllerButton;
+
+        let defaults = |action| match action {
+            Action::MoveForward => vec![key(Scancode::W), pad_axis(Axis::LeftY, false)],
+            Action::MoveBackward => vec![key(Scancode::S), pad_axis(Axis::LeftY, true)],
+            Action::MoveLeft => vec![key(Scancode::A), pad_axis(Axis::LeftX, false)],
+            Action::MoveRight => vec![key(Scancode::D), pad_axis(Axis::LeftX, true)],
+            Action::MoveUp => vec![key(Scancode::E), pad_button(Button::RightShoulder)],
+            Action::MoveDown => vec![key(Scancode::Q), pad_button(Button::LeftShoulder)],
+            Action::Sprint => vec![key(Scancode::LShift), pad_axis(Axis::TriggerRight, true)],
+            Action::Slow => vec![key(Scancode::LCtrl), pad_axis(Axis::TriggerLeft, true)],
+            Action::LookLeft => vec![pad_axis(Axis::RightX, false)],
+            Action::LookRight => vec![pad_axis(Axis::RightX, true)],
+            Action::LookUp => vec![pad_axis(Axis::RightY, false)],
+            Action::LookDown => vec![pad_axis(Axis::RightY, true)],
+            Action::CaptureMouse => vec![InputBinding::MouseButton(MouseButton::Left)],
+            Action::ReleaseMouse => vec![key(Scancode::Escape)],
+            Action::ToggleFullscreen => vec![key(Scancode::F11)],
+            Action::ToggleExclusiveFullscreen => vec![InputBinding::Key {
+                scancode: Scancode::Return, modifiers: KeyModifiers { alt: true, ..Default::default() } }],
+            Action::CycleToneMapper => vec![key(Scancode::T)],
+            Action::ToggleBloom => vec![key(Scancode::B)],
+            Action::ToggleAutoExposure => vec![key(Scancode::X)],
+            Action::ToggleSrgb => vec![key(Scancode::G)],
+            Action::ExposureUp => vec![key(Scancode::Equals)],
+            Action::ExposureDown => vec![key(Scancode::Minus)],
+            Action::CycleUpscaleFilter => vec![key(Scancode::U)],
+            Action::RenderScaleUp => vec![key(Scancode::RightBracket)],
+            Action::RenderScaleDown => vec![key(Scancode::LeftBracket)],
+            Action::CycleProjection => vec![key(Scancode::P), pad_button(Button::Back)],
+            Action::RecordCameraPath => vec![key(Scancode::K)],
+            Action::PlayCameraPath => vec![key(Scancode::L)],
+            Action::Screenshot => vec![key(Scancode::F2)],
+            Action::HighResStill => vec![key(Scancode::F3)],
+            Action::ToggleProfiler => vec![key(Scancode::F5)],
+            Action::ExportProfile => vec![key(Scancode::F6)],
+        };
+
+        InputBindings { bindings: Action::ALL.iter().map(|action| defaults(*action)).collect() }
+    }
+}
@@ -0,0 +1,119 @@
+use sdl2::{EventPump, GameControllerSubsystem, Sdl};
+use sdl2::controller::GameController;
+use sdl2::event::{Event, WindowEvent};
+use sdl2::mouse::MouseUtil;
+
+use crate::input::action::Action;
+use crate::input::bindings::{InputBinding, InputBindings};
+use crate::input::state::InputState;
+
+pub mod action;
+pub mod bindings;
+pub mod state;
+
+const AXIS_DEAD_ZONE: f32 = 0.2;
+
+/// Owns the SDL event pump and game controllers, turning their input into per frame action state
+/// so nothing else needs to deal with SDL events.
+pub struct Input {
+    pub bindings: InputBindings,
+    event_pump: EventPump,
+    mouse: MouseUtil,
+    controller_subsystem: Option<GameControllerSubsystem>,
+    controllers: Vec<GameController>,
+    state: InputState,
+}
+
+impl Input {
+    pub fn new(sdl: &Sdl, bindings: InputBindings) -> Input {
+        let event_pump = sdl.event_pump().unwrap();
+        let controller_subsystem = match sdl.game_controller() {
+            Ok(subsystem) => Some(subsystem),
+            Err(error) => {
+                println!("Game controllers unavailable: {}", error);
+                None
+            }
+        };
+
+        Input { bindings, event_pump, mouse: sdl.mouse(), controller_subsystem,
+            controllers: Vec::new(), state: InputState::default() }
+    }
+
+    /// Hides the cursor and reports relative mouse movement, for mouse look.
+    pub fn set_mouse_captured(&mut self, captured: bool) {
+        self.mouse.set_relative_mouse_mode(captured);
+    }
+
+    pub fn is_mouse_captured(&self) -> bool {
+        self.mouse.relative_mouse_mode()
+    }
+
+    /// Processes this frame's events and reads the state of every bound input. Call once per
+    /// frame, before anything looks at the state. Presses come from the events, so even ones
+    /// released before the end of a slow frame count, while held and analog values are polled.
+    pub fn update(&mut self) -> &InputState {
+        self.state.start_frame();
+
+        let captured = self.is_mouse_captured();
+        for event in self.event_pump.poll_iter() {
+            for action in Action::ALL {
+                if self.bindings.get(action).iter().any(|binding| binding.pressed_by(&event)) {
+                    self.state.set_pressed(action);
+                }
+            }
+            match event {
+                Event::Quit { .. } => self.state.quit_requested = true,
+                Event::Window { win_event: WindowEvent::SizeChanged(..), .. } =>
+                    self.state.window_resized = true,
+                Event::MouseMotion { xrel, yrel, .. } if captured => {
+                    self.state.mouse_delta.0 += xrel;
+                    self.state.mouse_delta.1 += yrel;
+                }
+                Event::ControllerDeviceAdded { which, .. } => {
+                    if let Some(subsystem) = &self.controller_subsystem {
+                        match subsystem.open(which) {
+                            Ok(controller) => {
+                                println!("Controller connected: {}", controller.name());
+                                self.controllers.push(controller);
+                            }
+                            Err(error) => println!("Failed to open controller: {}", error),
+                        }
+                    }
+                }
+                Event::ControllerDeviceRemoved { which, .. } =>
+                    self.controllers.retain(|controller| controller.instance_id() != which),
+                _ => {}
+            }
+        }
+
+        let keyboard = self.event_pump.keyboard_state();
+        let mouse = self.event_pump.mouse_state();
+        for action in Action::ALL {
+            let value = self.bindings.get(action).iter()
+                .map(|binding| match *binding {
+                    InputBinding::Key { scancode, modifiers } =>
+                        (keyboard.is_scancode_pressed(scancode) && modifiers.held(&keyboard))
+                            as i32 as f32,
+                    InputBinding::MouseButton(button) =>
+                        mouse.is_mouse_button_pressed(button) as i32 as f32,
+                    InputBinding::ControllerButton(button) => self.controllers.iter()
+                        .any(|controller| controller.button(button)) as i32 as f32,
+                    InputBinding::ControllerAxis { axis, positive } => self.controllers.iter()
+                        .map(|controller| {
+                            let value = controller.axis(axis) as f32 / i16::MAX as f32;
+                            let value = if positive { value } else { -value };
+                            ((value - AXIS_DEAD_ZONE) / (1.0 - AXIS_DEAD_ZONE)).clamp(0.0, 1.0)
+                        })
+                        .fold(0.0, f32::max),
+                })
+                .fold(0.0, f32::max);
+            self.state.set_value(action, value);
+        }
+
+        return &self.state;
+    }
+
+    pub fn state(&self) -> &InputState {
+        &self.state
+    }
+}
@@ -0,0 +1,72 @@
+use crate::input::action::Action;
+
+const HELD_THRESHOLD: f32 = 0.5;  // Analog inputs count as held past this
+
+/// What the player is doing this frame.
+#[derive(Debug, Clone)]
+pub struct InputState {
+    values: [f32; Action::ALL.len()],  // 0 to 1, analog for sticks and triggers
+    previous_values: [f32; Action::ALL.len()],
+    pressed: [bool; Action::ALL.len()],  // Pressed by this frame's events, even if released again
+    pub(crate) mouse_delta: (i32, i32),
+    pub(crate) quit_requested: bool,
+    pub(crate) window_resized: bool,
+}
+
+impl InputState {
+    pub(crate) fn start_frame(&mut self) {
+        self.previous_values = self.values;
+        self.pressed = [false; Action::ALL.len()];
+        self.mouse_delta = (0, 0);
+        self.window_resized = false;
+    }
+
+    pub(crate) fn set_value(&mut self, action: Action, value: f32) {
+        self.values[action.index()] = value;
+    }
+
+    pub(crate) fn set_pressed(&mut self, action: Action) {
+        self.pressed[action.index()] = true;
+    }
+
+    /// How strongly the action is held, from 0 to 1.
+    pub fn value(&self, action: Action) -> f32 {
+        self.values[action.index()]
+    }
+
+    pub fn is_held(&self, action: Action) -> bool {
+        self.value(action) > HELD_THRESHOLD
+    }
+
+    /// Whether the action started being held this frame, or was pressed and released within it.
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.pressed[action.index()]
+            || self.is_held(action) && self.previous_values[action.index()] <= HELD_THRESHOLD
+    }
+
+    /// Value of the positive action minus the negative one, e.g. for movement axes.
+    pub fn axis(&self, negative: Action, positive: Action) -> f32 {
+        self.value(positive) - self.value(negative)
+    }
+
+    /// Relative mouse movement in pixels, only reported while the mouse is captured.
+    pub fn mouse_delta(&self) -> (i32, i32) {
+        self.mouse_delta
+    }
+
+    pub fn quit_requested(&self) -> bool {
+        self.quit_requested
+    }
+
+    pub fn window_resized(&self) -> bool {
+        self.window_resized
+    }
+}
+
+impl Default for InputState {
+    fn default() -> InputState {
+        InputState { values: [0.0; Action::ALL.len()], previous_values: [0.0; Action::ALL.len()],
+            pressed: [false; Action::ALL.len()], mouse_delta: (0, 0), quit_requested: false,
+            window_resized: false }
+    }
+}
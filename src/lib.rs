@@ -0,0 +1,7 @@
+pub mod arguments;
+pub mod config;
+pub mod graphics;
+pub mod input;
+pub mod logger;
+pub mod maths;
+pub mod world;
\ No newline at end of file
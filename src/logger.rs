@@ -0,0 +1,35 @@
+use std::env;
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+const LEVEL_VARIABLE: &str = "HORT_MC_LOG";
+
+/// Writes log messages to stderr.
+struct StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("[{}] {}: {}", record.level(), record.target(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: StderrLogger = StderrLogger;
+
+/// Sends log messages to stderr, from the level in the HORT_MC_LOG environment variable (e.g.
+/// "debug") up, info by default. Does nothing if a logger has already been set.
+pub fn init() {
+    let level = env::var(LEVEL_VARIABLE).ok()
+        .and_then(|level| level.parse().ok())
+        .unwrap_or(LevelFilter::Info);
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(level);
+    }
+}
@@ -1,12 +1,28 @@
-use crate::graphics::screen_copier::texture_drawer::TextureDrawer;
-use crate::graphics::world_renderer::ray_tracer::RayTracer;
-
-pub mod graphics;
-pub mod world;
+use hort_mc::arguments::Arguments;
 
 fn main() {
-    let texture_drawer = TextureDrawer::new("Game", 900, 900);
-    let ray_tracer = RayTracer::new(Default::default(), Default::default());
+    hort_mc::logger::init();
+    let arguments = Arguments::from_env();
+    if arguments.gl_info {
+        hort_mc::graphics::print_gl_info();
+        return;
+    }
+    hort_mc::graphics::set_shader_dev_mode(arguments.dev_shaders);
+    hort_mc::graphics::set_shader_cache(arguments.shader_cache);
+    let headless_result = if arguments.frames.is_some() || arguments.video.is_some() {
+        Some(hort_mc::graphics::video_export::export_camera_path(&arguments))
+    } else if arguments.output.is_some() {
+        Some(hort_mc::graphics::headless::render_to_file(&arguments))
+    } else {
+        None
+    };
+    if let Some(result) = headless_result {
+        if let Err(error) = result {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+        return;
+    }
 
-    graphics::mainloop(texture_drawer, ray_tracer);
+    hort_mc::graphics::run(&arguments);
 }
\ No newline at end of file
@@ -0,0 +1,82 @@
+use std::ops::Mul;
+
+use std140::{mat4x4, vec4};
+
+use crate::maths::vector::Vec3;
+
+/// Column major 4x4 matrix, laid out the same as GLSL's mat4.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat4 {
+    pub columns: [[f32; 4]; 4],
+}
+
+impl Mat4 {
+    pub const IDENTITY: Mat4 = Mat4 { columns: [[1.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0, 0.0],
+                                                [0.0, 0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 1.0]] };
+
+    /// Affine transform with the given axes and translation.
+    pub fn from_columns(x: Vec3, y: Vec3, z: Vec3, translation: Vec3) -> Mat4 {
+        Mat4 { columns: [[x.x, x.y, x.z, 0.0], [y.x, y.y, y.z, 0.0], [z.x, z.y, z.z, 0.0],
+                         [translation.x, translation.y, translation.z, 1.0]] }
+    }
+
+    pub fn from_translation(translation: Vec3) -> Mat4 {
+        Mat4::from_columns(Vec3::X, Vec3::Y, Vec3::Z, translation)
+    }
+
+    pub fn column(&self, index: usize) -> Vec3 {
+        let column = self.columns[index];
+        Vec3::new(column[0], column[1], column[2])
+    }
+
+    pub fn transpose(&self) -> Mat4 {
+        let mut result = Mat4::IDENTITY;
+        for column in 0..4 {
+            for row in 0..4 {
+                result.columns[column][row] = self.columns[row][column];
+            }
+        }
+        result
+    }
+
+    /// Inverse of a rotation and translation only matrix, much cheaper than a general inverse.
+    pub fn rigid_inverse(&self) -> Mat4 {
+        let rotation = Mat4::from_columns(self.column(0), self.column(1), self.column(2),
+                                          Vec3::ZERO).transpose();
+        let translation = rotation.transform_vector(-self.column(3));
+        Mat4 { columns: [rotation.columns[0], rotation.columns[1], rotation.columns[2],
+                         [translation.x, translation.y, translation.z, 1.0]] }
+    }
+
+    pub fn transform_point(&self, point: Vec3) -> Vec3 {
+        self.transform_vector(point) + self.column(3)
+    }
+
+    /// Transforms a direction, ignoring translation.
+    pub fn transform_vector(&self, vector: Vec3) -> Vec3 {
+        self.column(0) * vector.x + self.column(1) * vector.y + self.column(2) * vector.z
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Mat4;
+
+    fn mul(self, other: Mat4) -> Mat4 {
+        let mut result = Mat4 { columns: [[0.0; 4]; 4] };
+        for column in 0..4 {
+            for row in 0..4 {
+                result.columns[column][row] = (0..4)
+                    .map(|i| self.columns[i][row] * other.columns[column][i])
+                    .sum();
+            }
+        }
+        result
+    }
+}
+
+impl From<Mat4> for mat4x4 {
+    fn from(matrix: Mat4) -> mat4x4 {
+        let [c0, c1, c2, c3] = matrix.columns.map(|c| vec4(c[0], c[1], c[2], c[3]));
+        std140::mat4x4(c0, c1, c2, c3)
+    }
+}
@@ -0,0 +1,109 @@
+use std::ops::Mul;
+
+use crate::maths::matrix::Mat4;
+use crate::maths::vector::Vec3;
+
+/// Unit quaternion representing a rotation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quaternion {
+    pub w: f32,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl Quaternion {
+    pub const IDENTITY: Quaternion = Quaternion { w: 1.0, x: 0.0, y: 0.0, z: 0.0 };
+
+    /// Rotation of angle radians around axis, following the right hand rule.
+    pub fn from_axis_angle(axis: Vec3, angle: f32) -> Quaternion {
+        let axis = axis.normalize();
+        let (sin, cos) = (angle / 2.0).sin_cos();
+        Quaternion { w: cos, x: axis.x * sin, y: axis.y * sin, z: axis.z * sin }
+    }
+
+    /// Yaw around Y, then pitch around X, then roll around Z, all in radians. Positive pitch
+    /// points +Z downwards.
+    pub fn from_yaw_pitch_roll(yaw: f32, pitch: f32, roll: f32) -> Quaternion {
+        Quaternion::from_axis_angle(Vec3::Y, yaw)
+            * Quaternion::from_axis_angle(Vec3::X, pitch)
+            * Quaternion::from_axis_angle(Vec3::Z, roll)
+    }
+
+    /// Inverse of from_yaw_pitch_roll, returns (yaw, pitch, roll) in radians.
+    pub fn to_yaw_pitch_roll(self) -> (f32, f32, f32) {
+        let forward = self.rotate(Vec3::Z);
+        let right = self.rotate(Vec3::X);
+        let up = self.rotate(Vec3::Y);
+        (forward.x.atan2(forward.z), (-forward.y).clamp(-1.0, 1.0).asin(), right.y.atan2(up.y))
+    }
+
+    pub fn dot(self, other: Quaternion) -> f32 {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn normalize(self) -> Quaternion {
+        let length = self.dot(self).sqrt();
+        if length == 0.0 {
+            return Quaternion::IDENTITY;
+        }
+        Quaternion { w: self.w / length, x: self.x / length, y: self.y / length,
+            z: self.z / length }
+    }
+
+    /// The opposite rotation.
+    pub fn conjugate(self) -> Quaternion {
+        Quaternion { w: self.w, x: -self.x, y: -self.y, z: -self.z }
+    }
+
+    pub fn rotate(self, vector: Vec3) -> Vec3 {
+        let axis = Vec3::new(self.x, self.y, self.z);
+        let t = axis.cross(vector) * 2.0;
+        vector + t * self.w + axis.cross(t)
+    }
+
+    /// Spherical interpolation, taking the shortest path between the two rotations.
+    pub fn slerp(self, other: Quaternion, t: f32) -> Quaternion {
+        let mut other = other;
+        let mut cos = self.dot(other);
+        if cos < 0.0 {
+            other = Quaternion { w: -other.w, x: -other.x, y: -other.y, z: -other.z };
+            cos = -cos;
+        }
+
+        let (a, b) = if cos > 0.9995 {  // Nearly the same, avoid dividing by sin of ~0
+            (1.0 - t, t)
+        } else {
+            let angle = cos.acos();
+            let sin = angle.sin();
+            (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+        };
+        Quaternion { w: self.w * a + other.w * b, x: self.x * a + other.x * b,
+            y: self.y * a + other.y * b, z: self.z * a + other.z * b }.normalize()
+    }
+
+    pub fn to_matrix(self) -> Mat4 {
+        Mat4::from_columns(self.rotate(Vec3::X), self.rotate(Vec3::Y), self.rotate(Vec3::Z),
+                           Vec3::ZERO)
+    }
+}
+
+impl Default for Quaternion {
+    fn default() -> Quaternion {
+        Quaternion::IDENTITY
+    }
+}
+
+impl Mul for Quaternion {
+    type Output = Quaternion;
+
+    /// Combined rotation, applying other first.
+    fn mul(self, other: Quaternion) -> Quaternion {
+        Quaternion {
+            w: self.w * other.w - self.x * other.x - self.y * other.y - self.z * other.z,
+            x: self.w * other.x + self.x * other.w + self.y * other.z - self.z * other.y,
+            y: self.w * other.y - self.x * other.z + self.y * other.w + self.z * other.x,
+            z: self.w * other.z + self.x * other.y - self.y * other.x + self.z * other.w,
+        }
+    }
+}
@@ -1,8 +1,95 @@
 use std::collections::HashMap;
 
+use crate::maths::vector::Vec3;
+
+pub const AIR: u32 = 0;
+const SECTION_SIZE: i32 = 16;  // Sections are 16x16x16 blocks, stacked into 16 wide chunks
+
+/// A box of blocks given by two corners, e.g. its lowest corner and its size.
+pub type BlockBox = ((i32, i32, i32), (i32, i32, i32));
+
 #[derive(Debug)]
 pub struct World {
-    pub chunks: HashMap<i64, Chunk>
+    pub chunks: HashMap<i64, Chunk>  // Keyed by chunk_key
+}
+
+impl World {
+    /// A small test scene of a floor, a wall and a light.
+    pub fn demo() -> World {
+        let mut world = World::default();
+        let start = (-5, -7, -5);  // world_start in the shader
+        let blocks = [
+            ((4, 4, 4), 2), ((4, 4, 5), 2), ((4, 4, 6), 2),  // Floor
+            ((5, 4, 4), 2), ((5, 4, 5), 3), ((5, 4, 6), 2),
+            ((6, 4, 4), 2), ((6, 4, 5), 2), ((6, 4, 6), 2),
+            ((4, 5, 7), 1), ((5, 5, 7), 2), ((6, 5, 7), 1),  // Wall
+            ((4, 6, 7), 1), ((5, 6, 7), 1), ((6, 6, 7), 1),
+            ((4, 7, 7), 2), ((5, 7, 7), 2), ((6, 7, 7), 2),
+        ];
+        for ((x, y, z), block) in blocks {
+            world.set_block(x + start.0, y + start.1, z + start.2, block);
+        }
+        world
+    }
+
+    /// Global pallet id of the block at a position, air outside of loaded sections.
+    pub fn block_at(&self, x: i32, y: i32, z: i32) -> u32 {
+        let Some(section_y) = section_index(y) else { return AIR };
+        self.chunks.get(&chunk_key(x.div_euclid(SECTION_SIZE), z.div_euclid(SECTION_SIZE)))
+            .and_then(|chunk| chunk.sections.get(&section_y))
+            .map_or(AIR, |section| section.block_at(x, y, z))
+    }
+
+    /// The block containing a point.
+    pub fn block_at_point(&self, point: Vec3) -> u32 {
+        let point = point.floor();
+        self.block_at(point.x as i32, point.y as i32, point.z as i32)
+    }
+
+    /// The smallest box holding every block that isn't air, as its lowest corner and its size.
+    /// None if there are no blocks.
+    pub fn bounds(&self) -> Option<BlockBox> {
+        let mut bounds: Option<BlockBox> = None;  // Lowest and highest corners while searching
+        for (key, chunk) in &self.chunks {
+            let (chunk_x, chunk_z) = chunk_coordinates(*key);
+            for (section_y, section) in chunk.sections.iter().filter(|(_, section)| section.block_count > 0) {
+                for index in 0..section.data.len() as i32 {
+                    let (x, y, z) = (chunk_x * SECTION_SIZE + index % SECTION_SIZE,
+                                     *section_y as i32 * SECTION_SIZE + index / (SECTION_SIZE * SECTION_SIZE),
+                                     chunk_z * SECTION_SIZE + index / SECTION_SIZE % SECTION_SIZE);
+                    if section.block_at(x, y, z) == AIR {
+                        continue;
+                    }
+                    let (min, max) = bounds.get_or_insert(((x, y, z), (x, y, z)));
+                    *min = (min.0.min(x), min.1.min(y), min.2.min(z));
+                    *max = (max.0.max(x), max.1.max(y), max.2.max(z));
+                }
+            }
+        }
+        bounds.map(|(min, max)| (min, (max.0 - min.0 + 1, max.1 - min.1 + 1, max.2 - min.2 + 1)))
+    }
+
+    /// Places a block, creating its chunk and section if needed. Positions outside the height
+    /// a section index can hold are ignored.
+    pub fn set_block(&mut self, x: i32, y: i32, z: i32, block: u32) {
+        let Some(section_y) = section_index(y) else { return };
+        let key = chunk_key(x.div_euclid(SECTION_SIZE), z.div_euclid(SECTION_SIZE));
+        let chunk = self.chunks.entry(key).or_insert_with(|| Chunk { sections: HashMap::new() });
+        chunk.sections.entry(section_y).or_default().set_block(x, y, z, block);
+    }
+}
+
+/// Key of the chunk with the given chunk coordinates in World::chunks.
+pub fn chunk_key(chunk_x: i32, chunk_z: i32) -> i64 {
+    ((chunk_x as i64) << 32) | (chunk_z as u32 as i64)
+}
+
+fn chunk_coordinates(key: i64) -> (i32, i32) {
+    ((key >> 32) as i32, key as i32)
+}
+
+fn section_index(y: i32) -> Option<i8> {
+    i8::try_from(y.div_euclid(SECTION_SIZE)).ok()
 }
 
 impl Default for World {
@@ -21,4 +108,45 @@ pub struct ChunkSection {
     pub block_count: u16,
     pub pallet: Vec<i32>,  // Stores indexes of global pallet
     pub data: [i32; 4096]
-}
\ No newline at end of file
+}
+impl ChunkSection {
+    pub fn new() -> ChunkSection {
+        ChunkSection { block_count: 0, pallet: vec![AIR as i32], data: [0; 4096] }
+    }
+
+    /// Index into data of a block, from world or section relative coordinates.
+    fn index(x: i32, y: i32, z: i32) -> usize {
+        let (x, y, z) = (x.rem_euclid(SECTION_SIZE), y.rem_euclid(SECTION_SIZE),
+                         z.rem_euclid(SECTION_SIZE));
+        ((y * SECTION_SIZE + z) * SECTION_SIZE + x) as usize
+    }
+
+    pub fn block_at(&self, x: i32, y: i32, z: i32) -> u32 {
+        self.pallet[self.data[ChunkSection::index(x, y, z)] as usize] as u32
+    }
+
+    pub fn set_block(&mut self, x: i32, y: i32, z: i32, block: u32) {
+        let pallet_index = match self.pallet.iter().position(|id| *id == block as i32) {
+            Some(index) => index,
+            None => {
+                self.pallet.push(block as i32);
+                self.pallet.len() - 1
+            }
+        };
+
+        let index = ChunkSection::index(x, y, z);
+        let was_air = self.block_at(x, y, z) == AIR;
+        self.data[index] = pallet_index as i32;
+        match (was_air, block == AIR) {
+            (true, false) => self.block_count += 1,
+            (false, true) => self.block_count -= 1,
+            _ => {}
+        }
+    }
+}
+
+impl Default for ChunkSection {
+    fn default() -> ChunkSection {
+        ChunkSection::new()
+    }
+}
@@ -0,0 +1,112 @@
+use std::env;
+use std::fs;
+use std::process;
+
+use hort_mc::graphics::world_renderer::camera_path::{CameraKeyframe, CameraPath};
+use hort_mc::maths::quaternion::Quaternion;
+use hort_mc::maths::vector::Vec3;
+
+const EPSILON: f32 = 1e-4;
+
+fn keyframe(time: f32, position: Vec3, yaw: f32, fov: f32) -> CameraKeyframe {
+    CameraKeyframe { time, position, orientation: Quaternion::from_yaw_pitch_roll(yaw.to_radians(), 0.0, 0.0),
+        fov }
+}
+
+fn assert_keyframes_close(actual: &CameraKeyframe, expected: &CameraKeyframe) {
+    assert!((actual.time - expected.time).abs() < EPSILON, "{:?} isn't close to {:?}", actual, expected);
+    assert!((actual.position - expected.position).length() < EPSILON, "{:?} isn't close to {:?}", actual,
+            expected);
+    assert!(actual.orientation.dot(expected.orientation).abs() > 1.0 - EPSILON, "{:?} isn't close to {:?}",
+            actual, expected);
+    assert!((actual.fov - expected.fov).abs() < EPSILON, "{:?} isn't close to {:?}", actual, expected);
+}
+
+/// Evenly spaced keyframes moving in a straight line at a constant speed.
+fn straight_path() -> CameraPath {
+    CameraPath::new(vec![
+        keyframe(2.0, Vec3::new(2.0, 0.0, 0.0), 20.0, 70.0),
+        keyframe(0.0, Vec3::ZERO, 0.0, 90.0),  // Out of order, to check they're sorted
+        keyframe(1.0, Vec3::new(1.0, 0.0, 0.0), 10.0, 80.0),
+        keyframe(3.0, Vec3::new(3.0, 0.0, 0.0), 30.0, 60.0),
+    ])
+}
+
+#[test]
+fn parses_keyframes() {
+    let path = CameraPath::parse("# time x y z yaw pitch roll fov\n\n1 1 2 3 90 0 0 60\n  0 0 0 0 0 0 0 90  \n")
+        .unwrap();
+    assert_eq!(path.keyframes().len(), 2);
+    assert_eq!(path.duration(), 1.0);
+    assert_keyframes_close(&path.keyframes()[0], &keyframe(0.0, Vec3::ZERO, 0.0, 90.0));
+    assert_keyframes_close(&path.keyframes()[1], &keyframe(1.0, Vec3::new(1.0, 2.0, 3.0), 90.0, 60.0));
+}
+
+#[test]
+fn parse_errors_give_the_line() {
+    let error = CameraPath::parse("0 0 0 0 0 0 0 90\n1 2 3\n").unwrap_err();
+    assert_eq!(error, "Expected 8 values but found 3 on line 2");
+    let error = CameraPath::parse("# comment\n0 0 zero 0 0 0 0 90\n").unwrap_err();
+    assert!(error.ends_with("on line 2"), "{}", error);
+}
+
+#[test]
+fn saved_paths_load_the_same() {
+    let mut path = straight_path();
+    path.push(CameraKeyframe { time: 4.0, position: Vec3::new(-1.5, 2.25, 0.125),
+        orientation: Quaternion::from_yaw_pitch_roll(-2.0, 0.5, 0.25), fov: 45.5 });
+
+    let file = env::temp_dir().join(format!("hort_mc_camera_path_{}.txt", process::id()));
+    path.save(&file).unwrap();
+    let loaded = CameraPath::load(&file);
+    fs::remove_file(&file).unwrap();
+
+    let loaded = loaded.unwrap();
+    assert_eq!(loaded.keyframes().len(), path.keyframes().len());
+    for (actual, expected) in loaded.keyframes().iter().zip(path.keyframes()) {
+        assert_keyframes_close(actual, expected);
+    }
+}
+
+#[test]
+fn samples_at_keyframes_are_the_keyframes() {
+    let path = straight_path();
+    for keyframe in path.keyframes() {
+        assert_keyframes_close(&path.sample(keyframe.time).unwrap(), keyframe);
+    }
+}
+
+#[test]
+fn samples_are_clamped_to_the_ends() {
+    let path = straight_path();
+    let (first, last) = (path.keyframes()[0], path.keyframes()[3]);
+    assert_keyframes_close(&path.sample(-5.0).unwrap(), &CameraKeyframe { time: -5.0, ..first });
+    assert_keyframes_close(&path.sample(10.0).unwrap(), &CameraKeyframe { time: 10.0, ..last });
+    assert!(CameraPath::default().sample(0.0).is_none());
+}
+
+#[test]
+fn even_straight_motion_stays_straight_between_keyframes() {
+    // Catmull-Rom reproduces constant speed motion, including in the first and last spans
+    let path = straight_path();
+    for time in [0.25, 0.5, 1.5, 2.75] {
+        let expected = keyframe(time, Vec3::new(time, 0.0, 0.0), time * 10.0, 90.0 - time * 10.0);
+        assert_keyframes_close(&path.sample(time).unwrap(), &expected);
+    }
+}
+
+#[test]
+fn curves_pass_smoothly_through_keyframes() {
+    let path = CameraPath::new(vec![
+        keyframe(0.0, Vec3::ZERO, 0.0, 90.0),
+        keyframe(1.0, Vec3::new(1.0, 1.0, 0.0), 0.0, 90.0),
+        keyframe(2.0, Vec3::new(2.0, 0.0, 0.0), 0.0, 90.0),
+    ]);
+    // The middle keyframe's tangent is flat in y, so just either side of it is lower but level
+    let before = path.sample(0.99).unwrap().position;
+    let after = path.sample(1.01).unwrap().position;
+    assert!(before.y < 1.0 && after.y < 1.0);
+    assert!((before.y - after.y).abs() < EPSILON);
+    // Overshoot past a straight line between keyframes, as a spline should
+    assert!(path.sample(0.5).unwrap().position.y > 0.5);
+}
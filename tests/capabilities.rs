@@ -0,0 +1,49 @@
+use hort_mc::graphics::capabilities::GlCapabilities;
+
+fn capabilities(version: (u32, u32), extensions: &[&str]) -> GlCapabilities {
+    GlCapabilities { version, extensions: extensions.iter().map(|extension| extension.to_string()).collect(),
+        ..Default::default() }
+}
+
+#[test]
+fn gpu_renderer_needs_gl_4_6() {
+    assert!(capabilities((4, 6), &[]).missing_for_gpu_renderer().is_empty());
+    assert!(capabilities((5, 0), &[]).missing_for_gpu_renderer().is_empty());
+
+    // macOS stops at 4.1, without compute shaders
+    let missing = capabilities((4, 1), &[]).missing_for_gpu_renderer();
+    assert_eq!(missing.len(), 2);
+    assert!(missing[0].contains("compute"));
+    assert!(missing[1].contains("4.6"));
+}
+
+#[test]
+fn extensions_add_features_to_older_versions() {
+    let old = capabilities((3, 3), &["GL_ARB_compute_shader", "GL_KHR_debug"]);
+    assert!(old.compute_shaders());
+    assert!(old.debug_output());
+    assert_eq!(old.missing_for_gpu_renderer().len(), 1);
+
+    let plain = capabilities((3, 3), &[]);
+    assert!(!plain.compute_shaders());
+    assert!(!plain.debug_output());
+    assert!(!plain.has_extension("GL_ARB_compute"));  // Only whole names match
+}
+
+#[test]
+fn info_lists_limits_and_extensions() {
+    let mut gl = capabilities((4, 1), &["GL_ARB_debug_output"]);
+    gl.limits = vec![("MAX_TEXTURE_SIZE", 16384)];
+    let info = gl.to_string();
+    assert!(info.contains("GPU renderer: unsupported, needs compute shaders"));
+    assert!(info.contains("MAX_TEXTURE_SIZE 16384"));
+    assert!(info.ends_with("Extensions (1):\n  GL_ARB_debug_output"));
+}
+
+#[test]
+fn limits_are_looked_up_by_name() {
+    let mut gl = capabilities((4, 6), &[]);
+    gl.limits = vec![("MAX_TEXTURE_SIZE", 16384), ("MAX_IMAGE_UNITS", 8)];
+    assert_eq!(gl.limit("MAX_IMAGE_UNITS"), Some(8));
+    assert_eq!(gl.limit("MAX_LABEL_LENGTH"), None);
+}
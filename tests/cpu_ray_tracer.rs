@@ -0,0 +1,44 @@
+use hort_mc::graphics::world_renderer::camera::{Camera, Projection};
+use hort_mc::graphics::world_renderer::cpu_ray_tracer::{CpuRayTracer, CpuRayTracerSettings};
+use hort_mc::maths::vector::Vec3;
+use hort_mc::world::World;
+
+const AMBIENT: [f32; 4] = [0.7, 0.7, 1.0, 1.0];
+
+fn demo_camera() -> Camera {
+    Camera::new(Vec3::new(0.5, 1.0, -2.0), 30.0, 10.0, 0.0,
+                Projection::Perspective { vertical_fov: 90.0 })
+}
+
+#[test]
+fn empty_world_is_all_sky() {
+    let tracer = CpuRayTracer::new(demo_camera(), Default::default());
+    let image = tracer.render(&World::default(), 16, 16, 1);
+    assert!(image.pixels().all(|pixel| pixel.0 == AMBIENT));
+}
+
+#[test]
+fn demo_world_is_visible() {
+    let tracer = CpuRayTracer::new(demo_camera(), Default::default());
+    let image = tracer.render(&World::demo(), 32, 32, 1);
+    assert!(image.pixels().any(|pixel| pixel.0 != AMBIENT));
+}
+
+#[test]
+fn fisheye_is_black_outside_the_image_circle() {
+    let camera = Camera::new(Vec3::ZERO, 0.0, 0.0, 0.0, Projection::Fisheye { fov: 180.0 });
+    let tracer = CpuRayTracer::new(camera, Default::default());
+    let image = tracer.render(&World::demo(), 16, 16, 1);
+    assert_eq!(image.get_pixel(0, 0).0, [0.0, 0.0, 0.0, 1.0]);
+}
+
+#[test]
+fn same_seed_gives_the_same_image_on_any_number_of_threads() {
+    let render = |threads, tile_size| {
+        let settings = CpuRayTracerSettings { tile_size, threads: Some(threads), seed: 7 };
+        CpuRayTracer::new(demo_camera(), settings).render(&World::demo(), 24, 20, 4)
+    };
+    let single = render(1, (24, 20));
+    assert_eq!(single, render(4, (5, 7)));
+    assert_eq!(single, render(3, (32, 32)));
+}
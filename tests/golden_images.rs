@@ -0,0 +1,186 @@
+//! Renders fixed scenes headless and compares them to the reference images in tests/golden,
+//! so changes to the shaders that alter the output get noticed.
+//!
+//! Images are compared with SSIM, so small differences between drivers (e.g. llvmpipe in CI and
+//! a real GPU locally) pass but anything structural fails. On failure the render and a diff image
+//! are written to target/golden_failures. Run with UPDATE_GOLDEN=1 to replace the references
+//! after an intended change, then check the new images before committing them. The references
+//! were made with llvmpipe, which before newer Mesa versions needs MESA_GL_VERSION_OVERRIDE=4.6
+//! and MESA_GLSL_VERSION_OVERRIDE=460 to offer the GL 4.6 the shaders need.
+//!
+//! SDL can only be used from one thread at a time, so this has its own main instead of the test
+//! harness and runs the scenes one after another.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use image::{GrayImage, Luma, RgbaImage};
+
+use hort_mc::graphics::error_handler::{self, DebugOutputSettings};
+use hort_mc::graphics::headless::{render_image, HeadlessContext};
+use hort_mc::graphics::post_processing::post_processor::PostProcessor;
+use hort_mc::graphics::world_renderer::camera::{Camera, Projection};
+use hort_mc::graphics::world_renderer::ray_tracer::{RayTracer, RayTracerSettings};
+use hort_mc::maths::vector::Vec3;
+use hort_mc::world::World;
+
+const WIDTH: u32 = 128;
+const HEIGHT: u32 = 128;
+const SAMPLES: u32 = 16;
+const SEED: u32 = 1;
+const MIN_SSIM: f64 = 0.98;  // Below this the image counts as changed
+const SSIM_WINDOW: u32 = 8;
+
+struct Scene {
+    name: &'static str,
+    camera: Camera,
+}
+
+fn scenes() -> Vec<Scene> {
+    let perspective = Projection::Perspective { vertical_fov: 90.0 };
+    vec![
+        Scene { name: "default_camera", camera: Camera::default() },
+        Scene { name: "looking_down",
+            camera: Camera::new(Vec3::new(0.5, 1.0, -2.0), 30.0, 10.0, 0.0, perspective) },
+        Scene { name: "rolled",
+            camera: Camera::new(Vec3::new(-1.0, -0.5, -1.5), 10.0, 20.0, 15.0, perspective) },
+        Scene { name: "fisheye",
+            camera: Camera::new(Vec3::ZERO, 20.0, 0.0, 0.0, Projection::Fisheye { fov: 180.0 }) },
+        Scene { name: "equirectangular",
+            camera: Camera::new(Vec3::ZERO, 0.0, 0.0, 0.0, Projection::Equirectangular) },
+        Scene { name: "orthographic",
+            camera: Camera::new(Vec3::new(0.0, 2.0, 0.0), 89.0, 0.0, 0.0,
+                                Projection::Orthographic { height: 12.0 }) },
+    ]
+}
+
+fn main() -> ExitCode {
+    let update = env::var_os("UPDATE_GOLDEN").is_some();
+    let golden_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden");
+    let failure_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target").join("golden_failures");
+
+    hort_mc::logger::init();
+    // Always build from source, so the images test the shaders as they are now
+    hort_mc::graphics::set_shader_cache(false);
+    // Any GL error fails the run, even if the image still matches
+    error_handler::configure(DebugOutputSettings { strict: true, ..Default::default() });
+    let _context = HeadlessContext::new();
+    let mut post_processor = PostProcessor::new(Default::default());
+    let settings = RayTracerSettings { seed: SEED, ..Default::default() };
+
+    let mut failures = 0;
+    for scene in scenes() {
+        let mut ray_tracer = RayTracer::new(scene.camera, World::demo(), settings.clone());
+        let image = render_image(&mut ray_tracer, &mut post_processor, WIDTH, HEIGHT, SAMPLES).ldr;
+        error_handler::check();
+        let reference_path = golden_dir.join(format!("{}.png", scene.name));
+
+        let result = if update {
+            save(&image, &reference_path).map(|()| "updated".to_string())
+        } else {
+            check(scene.name, &image, &reference_path, &failure_dir)
+        };
+        match result {
+            Ok(message) => println!("{} ... {}", scene.name, message),
+            Err(message) => {
+                println!("{} ... FAILED: {}", scene.name, message);
+                failures += 1;
+            }
+        }
+    }
+
+    if failures > 0 {
+        println!("\n{} golden image test(s) failed, see {}", failures, failure_dir.display());
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+fn check(name: &str, image: &RgbaImage, reference_path: &Path,
+         failure_dir: &Path) -> Result<String, String> {
+    let failure_path = |suffix: &str| -> PathBuf { failure_dir.join(format!("{}_{}.png", name, suffix)) };
+
+    if !reference_path.exists() {
+        save(image, reference_path)?;
+        return Err(format!("no reference image, wrote {} to be checked and committed",
+                           reference_path.display()));
+    }
+    let reference = image::open(reference_path)
+        .map_err(|error| format!("failed to load {}: {}", reference_path.display(), error))?
+        .into_rgba8();
+    if reference.dimensions() != image.dimensions() {
+        save(image, &failure_path("actual"))?;
+        return Err(format!("size is {:?} but the reference is {:?}", image.dimensions(),
+                           reference.dimensions()));
+    }
+
+    let score = ssim(&luminance(image), &luminance(&reference));
+    if score < MIN_SSIM {
+        save(image, &failure_path("actual"))?;
+        save(&diff_image(image, &reference), &failure_path("diff"))?;
+        return Err(format!("SSIM {:.4} is below {}", score, MIN_SSIM));
+    }
+    Ok(format!("ok (SSIM {:.4})", score))
+}
+
+fn save(image: &RgbaImage, path: &Path) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|error| error.to_string())?;
+    }
+    image.save(path).map_err(|error| format!("failed to save {}: {}", path.display(), error))
+}
+
+fn luminance(image: &RgbaImage) -> GrayImage {
+    GrayImage::from_fn(image.width(), image.height(), |x, y| {
+        let [r, g, b, _] = image.get_pixel(x, y).0;
+        let value = 0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32;
+        Luma([value.round() as u8])
+    })
+}
+
+/// Mean structural similarity over non-overlapping windows, 1 meaning identical.
+fn ssim(a: &GrayImage, b: &GrayImage) -> f64 {
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+    let mut total = 0.0;
+    let mut windows = 0;
+    for window_y in (0..a.height()).step_by(SSIM_WINDOW as usize) {
+        for window_x in (0..a.width()).step_by(SSIM_WINDOW as usize) {
+            let pixels: Vec<(f64, f64)> = (window_y..(window_y + SSIM_WINDOW).min(a.height()))
+                .flat_map(|y| (window_x..(window_x + SSIM_WINDOW).min(a.width()))
+                    .map(move |x| (x, y)))
+                .map(|(x, y)| (a.get_pixel(x, y).0[0] as f64, b.get_pixel(x, y).0[0] as f64))
+                .collect();
+            let count = pixels.len() as f64;
+
+            let mean_a = pixels.iter().map(|(a, _)| a).sum::<f64>() / count;
+            let mean_b = pixels.iter().map(|(_, b)| b).sum::<f64>() / count;
+            let (mut variance_a, mut variance_b, mut covariance) = (0.0, 0.0, 0.0);
+            for (a, b) in &pixels {
+                variance_a += (a - mean_a) * (a - mean_a);
+                variance_b += (b - mean_b) * (b - mean_b);
+                covariance += (a - mean_a) * (b - mean_b);
+            }
+            variance_a /= count;
+            variance_b /= count;
+            covariance /= count;
+
+            total += ((2.0 * mean_a * mean_b + C1) * (2.0 * covariance + C2))
+                / ((mean_a * mean_a + mean_b * mean_b + C1) * (variance_a + variance_b + C2));
+            windows += 1;
+        }
+    }
+    total / windows as f64
+}
+
+/// Absolute difference per channel, brightened so small differences are visible.
+fn diff_image(a: &RgbaImage, b: &RgbaImage) -> RgbaImage {
+    RgbaImage::from_fn(a.width(), a.height(), |x, y| {
+        let (a, b) = (a.get_pixel(x, y).0, b.get_pixel(x, y).0);
+        let channel = |i: usize| (a[i].abs_diff(b[i]) as u32 * 4).min(255) as u8;
+        image::Rgba([channel(0), channel(1), channel(2), 255])
+    })
+}
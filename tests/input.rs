@@ -0,0 +1,53 @@
+use std::env;
+use std::fs;
+use std::process;
+
+use sdl2::event::Event;
+use sdl2::keyboard::{Keycode, Mod, Scancode};
+
+use hort_mc::input::action::Action;
+use hort_mc::input::bindings::InputBindings;
+use hort_mc::input::Input;
+
+fn key_down(scancode: Scancode) -> Event {
+    Event::KeyDown { timestamp: 0, window_id: 0, keycode: Keycode::from_scancode(scancode),
+        scancode: Some(scancode), keymod: Mod::empty(), repeat: false }
+}
+
+fn key_up(scancode: Scancode) -> Event {
+    Event::KeyUp { timestamp: 0, window_id: 0, keycode: Keycode::from_scancode(scancode),
+        scancode: Some(scancode), keymod: Mod::empty(), repeat: false }
+}
+
+// SDL only allows one event pump at a time, so this is the only test using one
+#[test]
+fn taps_within_a_frame_are_just_pressed() {
+    let sdl = sdl2::init().unwrap();
+    let events = sdl.event().unwrap();
+    let mut input = Input::new(&sdl, InputBindings::default());
+
+    events.push_event(key_down(Scancode::F2)).unwrap();
+    events.push_event(key_up(Scancode::F2)).unwrap();
+    let state = input.update();
+    assert!(state.just_pressed(Action::Screenshot));
+    assert!(!state.is_held(Action::Screenshot));
+    assert!(!state.just_pressed(Action::HighResStill));
+
+    assert!(!input.update().just_pressed(Action::Screenshot));  // Only counted for one frame
+}
+
+#[test]
+fn bindings_files_without_bindings_are_kept() {
+    let config_home = env::temp_dir().join(format!("hort_mc_input_test_{}", process::id()));
+    let path = config_home.join("hort_mc").join("bindings.cfg");
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let contents = "# Everything commented out while trying things\n# Screenshot = F12\n";
+    fs::write(&path, contents).unwrap();
+    env::set_var("XDG_CONFIG_HOME", &config_home);
+
+    let bindings = InputBindings::load();
+    let kept = fs::read_to_string(&path).unwrap();
+    fs::remove_dir_all(&config_home).unwrap();
+    assert_eq!(kept, contents);
+    assert_eq!(bindings.get(Action::Screenshot), InputBindings::default().get(Action::Screenshot));
+}
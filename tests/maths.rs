@@ -0,0 +1,125 @@
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+use hort_mc::maths::matrix::Mat4;
+use hort_mc::maths::quaternion::Quaternion;
+use hort_mc::maths::vector::Vec3;
+
+const EPSILON: f32 = 1e-5;
+
+fn assert_close(actual: Vec3, expected: Vec3) {
+    assert!((actual - expected).length() < EPSILON, "{:?} isn't close to {:?}", actual, expected);
+}
+
+fn assert_same_rotation(actual: Quaternion, expected: Quaternion) {
+    // q and -q are the same rotation
+    assert!(actual.dot(expected).abs() > 1.0 - EPSILON, "{:?} isn't close to {:?}", actual, expected);
+}
+
+fn assert_matrix_close(actual: Mat4, expected: Mat4) {
+    for (actual, expected) in actual.columns.iter().flatten().zip(expected.columns.iter().flatten()) {
+        assert!((actual - expected).abs() < EPSILON, "{:?} isn't close to {:?}", actual, expected);
+    }
+}
+
+#[test]
+fn vector_products() {
+    assert_eq!(Vec3::X.cross(Vec3::Y), Vec3::Z);
+    assert_eq!(Vec3::Y.cross(Vec3::X), -Vec3::Z);
+    assert_eq!(Vec3::new(1.0, 2.0, 3.0).dot(Vec3::new(4.0, -5.0, 6.0)), 12.0);
+    assert_eq!(Vec3::new(3.0, 0.0, 4.0).length(), 5.0);
+    assert_eq!(Vec3::new(0.0, 0.0, -2.0).normalize(), -Vec3::Z);
+    assert_eq!(Vec3::ZERO.normalize(), Vec3::ZERO);
+    assert_eq!(Vec3::ZERO.lerp(Vec3::new(2.0, 4.0, 6.0), 0.5), Vec3::new(1.0, 2.0, 3.0));
+}
+
+#[test]
+fn axis_angle_follows_the_right_hand_rule() {
+    assert_close(Quaternion::from_axis_angle(Vec3::Y, FRAC_PI_2).rotate(Vec3::Z), Vec3::X);
+    assert_close(Quaternion::from_axis_angle(Vec3::Z, FRAC_PI_2).rotate(Vec3::X), Vec3::Y);
+    assert_close(Quaternion::from_axis_angle(Vec3::X, FRAC_PI_2).rotate(Vec3::Y), Vec3::Z);
+    // The axis doesn't need to be normalised
+    assert_close(Quaternion::from_axis_angle(Vec3::new(0.0, 3.0, 0.0), PI).rotate(Vec3::X), -Vec3::X);
+}
+
+#[test]
+fn multiplying_applies_the_right_rotation_first() {
+    let yaw = Quaternion::from_axis_angle(Vec3::Y, FRAC_PI_2);
+    let roll = Quaternion::from_axis_angle(Vec3::Z, FRAC_PI_2);
+    let rotation = yaw * roll;
+    assert_close(rotation.rotate(Vec3::X), yaw.rotate(roll.rotate(Vec3::X)));
+    assert_close(rotation.rotate(Vec3::X), Vec3::Y);
+    assert_close((rotation * rotation.conjugate()).rotate(Vec3::new(1.0, 2.0, 3.0)), Vec3::new(1.0, 2.0, 3.0));
+}
+
+#[test]
+fn positive_pitch_looks_down() {
+    let forward = Quaternion::from_yaw_pitch_roll(0.0, FRAC_PI_4, 0.0).rotate(Vec3::Z);
+    assert!(forward.y < 0.0);
+    assert_close(forward, Vec3::new(0.0, -FRAC_PI_4.sin(), FRAC_PI_4.cos()));
+}
+
+#[test]
+fn yaw_pitch_roll_round_trips() {
+    for (yaw, pitch, roll) in [(0.0, 0.0, 0.0), (0.5, 0.2, -0.3), (-2.5, -1.2, 1.0), (3.0, 1.5, -3.0)] {
+        let (actual_yaw, actual_pitch, actual_roll) = Quaternion::from_yaw_pitch_roll(yaw, pitch, roll)
+            .to_yaw_pitch_roll();
+        for (actual, expected) in [(actual_yaw, yaw), (actual_pitch, pitch), (actual_roll, roll)] {
+            assert!((actual - expected).abs() < 1e-4, "{} isn't close to {}", actual, expected);
+        }
+    }
+}
+
+#[test]
+fn slerp_goes_the_short_way_at_a_constant_rate() {
+    let start = Quaternion::IDENTITY;
+    let end = Quaternion::from_axis_angle(Vec3::Y, FRAC_PI_2);
+    assert_same_rotation(start.slerp(end, 0.0), start);
+    assert_same_rotation(start.slerp(end, 1.0), end);
+    assert_same_rotation(start.slerp(end, 0.5), Quaternion::from_axis_angle(Vec3::Y, FRAC_PI_4));
+    assert_same_rotation(start.slerp(end, 0.25), Quaternion::from_axis_angle(Vec3::Y, FRAC_PI_2 * 0.25));
+
+    // -end is the same rotation, so this shouldn't go the long way round
+    let negated = Quaternion { w: -end.w, x: -end.x, y: -end.y, z: -end.z };
+    assert_same_rotation(start.slerp(negated, 0.5), Quaternion::from_axis_angle(Vec3::Y, FRAC_PI_4));
+
+    // Nearly the same rotations fall back to a lerp
+    let close = Quaternion::from_axis_angle(Vec3::Y, 0.001);
+    assert_same_rotation(start.slerp(close, 0.5), Quaternion::from_axis_angle(Vec3::Y, 0.0005));
+}
+
+#[test]
+fn rotation_matrix_matches_the_quaternion() {
+    let rotation = Quaternion::from_yaw_pitch_roll(0.7, -0.4, 0.2);
+    let matrix = rotation.to_matrix();
+    for vector in [Vec3::X, Vec3::Y, Vec3::Z, Vec3::new(1.0, -2.0, 0.5)] {
+        assert_close(matrix.transform_vector(vector), rotation.rotate(vector));
+        assert_close(matrix.transform_point(vector), rotation.rotate(vector));
+    }
+}
+
+#[test]
+fn matrix_products_and_translation() {
+    let translation = Mat4::from_translation(Vec3::new(1.0, 2.0, 3.0));
+    assert_eq!(translation.transform_point(Vec3::X), Vec3::new(2.0, 2.0, 3.0));
+    assert_eq!(translation.transform_vector(Vec3::X), Vec3::X);
+    assert_eq!(translation * Mat4::IDENTITY, translation);
+    assert_eq!(Mat4::IDENTITY * translation, translation);
+    assert_eq!(translation.transpose().transpose(), translation);
+
+    // The right matrix is applied first
+    let rotation = Quaternion::from_axis_angle(Vec3::Z, FRAC_PI_2).to_matrix();
+    assert_close((translation * rotation).transform_point(Vec3::X), Vec3::new(1.0, 3.0, 3.0));
+    assert_close((rotation * translation).transform_point(Vec3::X), Vec3::new(-2.0, 2.0, 3.0));
+}
+
+#[test]
+fn rigid_inverse_undoes_the_transform() {
+    let rotation = Quaternion::from_yaw_pitch_roll(1.1, 0.3, -0.6).to_matrix();
+    let transform = Mat4::from_translation(Vec3::new(-4.0, 0.5, 2.0)) * rotation;
+    let inverse = transform.rigid_inverse();
+    assert_matrix_close(inverse * transform, Mat4::IDENTITY);
+    assert_matrix_close(transform * inverse, Mat4::IDENTITY);
+
+    let point = Vec3::new(3.0, -1.0, 7.0);
+    assert_close(inverse.transform_point(transform.transform_point(point)), point);
+}
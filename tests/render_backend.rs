@@ -0,0 +1,95 @@
+use image::{Rgba, Rgba32FImage};
+
+use hort_mc::graphics::backend::{RenderBackend, SoftwareBackend, SoftwareSurface, TextureFormat};
+use hort_mc::graphics::headless::accumulate;
+use hort_mc::graphics::screen_copier::texture_drawer::TextureDrawer;
+use hort_mc::graphics::world_renderer::camera::{Camera, Projection};
+use hort_mc::graphics::world_renderer::cpu_ray_tracer::{CpuRayTracer, CpuRayTracerSettings};
+use hort_mc::graphics::world_renderer::ray_tracer::{RayTracer, RayTracerSettings};
+use hort_mc::maths::vector::Vec3;
+use hort_mc::world::World;
+
+const SEED: u32 = 5;
+
+fn demo_camera(width: u32, height: u32) -> Camera {
+    let mut camera = Camera::new(Vec3::new(0.5, 1.0, -2.0), 30.0, 10.0, 0.0,
+                                 Projection::Perspective { vertical_fov: 90.0 });
+    camera.set_aspect_ratio(width, height);
+    camera
+}
+
+fn software_ray_tracer(width: u32, height: u32, settings: RayTracerSettings) -> RayTracer<SoftwareBackend> {
+    let backend = SoftwareBackend { threads: Some(3) };
+    RayTracer::with_backend(backend, demo_camera(width, height), World::demo(), settings)
+}
+
+#[test]
+fn software_ray_tracer_matches_cpu_ray_tracer() {
+    let (width, height) = (20, 12);
+    let backend = SoftwareBackend::default();
+    let texture = backend.create_texture(width, height, TextureFormat::Rgba32F, "Ray traced image");
+    let settings = RayTracerSettings { seed: SEED, ..Default::default() };
+    let mut ray_tracer = software_ray_tracer(width, height, settings);
+    accumulate(&mut ray_tracer, &texture, 3);
+
+    let settings = CpuRayTracerSettings { seed: SEED, ..Default::default() };
+    let expected = CpuRayTracer::new(demo_camera(width, height), settings)
+        .render(&World::demo(), width, height, 3);
+    assert_eq!(backend.read_texture(&texture), expected);
+}
+
+#[test]
+fn progressive_rendering_finishes_after_every_tile() {
+    let (width, height) = (16, 10);
+    let backend = SoftwareBackend::default();
+    let settings = RayTracerSettings { seed: SEED, tile_size: Some((8, 4)), tiles_per_frame: Some(2),
+        ..Default::default() };
+    let mut ray_tracer = software_ray_tracer(width, height, settings);
+    let texture = backend.create_texture(width, height, TextureFormat::Rgba32F, "Progressive");
+    let finished: Vec<bool> = (0..3).map(|_| ray_tracer.render_to(&texture)).collect();
+    assert_eq!(finished, [false, false, true]);  // 6 tiles, 2 a frame
+
+    let whole = backend.create_texture(width, height, TextureFormat::Rgba32F, "Whole");
+    let settings = RayTracerSettings { seed: SEED, ..Default::default() };
+    let mut ray_tracer = software_ray_tracer(width, height, settings);
+    assert!(ray_tracer.render_to(&whole));
+    assert_eq!(backend.read_texture(&texture), backend.read_texture(&whole));
+}
+
+#[test]
+fn texture_drawer_stretches_and_blends() {
+    let backend = SoftwareBackend::default();
+    let red = backend.create_texture(1, 1, TextureFormat::Rgba8, "Red");
+    backend.write_texture(&red, &Rgba32FImage::from_pixel(1, 1, Rgba([1.0, 0.0, 0.0, 1.0])));
+    let blue = backend.create_texture(1, 1, TextureFormat::Rgba8, "Half blue");
+    backend.write_texture(&blue, &Rgba32FImage::from_pixel(1, 1, Rgba([0.0, 0.0, 1.0, 0.5])));
+
+    let mut drawer = TextureDrawer::with_backend(backend, SoftwareSurface::new(4, 4));
+    drawer.draw(&red);
+    drawer.draw_overlay(&blue, 0, 0, 2, 2);
+    drawer.present();
+
+    let image = drawer.surface().presented();
+    let half = 128.0 / 255.0;  // Quantised by the Rgba8 texture
+    assert_eq!(image.get_pixel(3, 0).0, [1.0, 0.0, 0.0, 1.0]);  // The top right isn't overlaid
+    assert_eq!(image.get_pixel(0, 3).0, [1.0 - half, 0.0, half, half * half + (1.0 - half)]);
+    assert_eq!(drawer.surface().frames(), 1);
+}
+
+#[test]
+fn textures_read_back_what_was_written() {
+    let backend = SoftwareBackend::default();
+    let image = Rgba32FImage::from_fn(3, 2, |x, y| Rgba([x as f32, y as f32, 2.5, 1.0]));
+    let texture = backend.create_texture(3, 2, TextureFormat::Rgba32F, "Written");
+    backend.write_texture(&texture, &image);
+    assert_eq!(backend.texture_size(&texture), (3, 2));
+    assert_eq!(backend.read_texture(&texture), image);
+}
+
+#[test]
+fn buffer_writes_replace_a_range() {
+    let backend = SoftwareBackend::default();
+    let mut buffer = backend.create_buffer(vec![[0.0f32; 4]; 4], "Written");
+    backend.write_buffer(&mut buffer, 1, &[[1.0, 2.0, 3.0, 4.0], [5.0, 6.0, 7.0, 8.0]]);
+    assert_eq!(backend.read_buffer(&buffer), [[0.0; 4], [1.0, 2.0, 3.0, 4.0], [5.0, 6.0, 7.0, 8.0], [0.0; 4]]);
+}
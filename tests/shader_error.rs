@@ -0,0 +1,51 @@
+use hort_mc::graphics::shader_utils::error::{ShaderError, ShaderStage};
+use hort_mc::graphics::shader_utils::hot_reload::ShaderFile;
+use hort_mc::graphics::shader_utils::preprocessor::preprocess;
+
+const SOURCE: &str = "#version 460 core\n#include \"math.glsl\"\n\nvoid main() {\n    float x = 1.0\n}\n";
+
+fn error_for(log: &str) -> ShaderError {
+    let file = ShaderFile::new(SOURCE, file!(), "test.comp");
+    let source = preprocess(file, &[("WORKGROUP_SIZE", String::from("16"))]).unwrap();
+    let line = source.source.lines().position(|line| line.contains("float x")).unwrap() + 1;
+    ShaderError::from_log(ShaderStage::Compute, "test.comp", &source, &log.replace("LINE", &line.to_string()))
+}
+
+#[test]
+fn mesa_logs_are_parsed() {
+    let error = error_for("0:LINE(18): error: syntax error, unexpected '}', expecting ',' or ';'\n");
+    assert_eq!((error.file, error.line, error.column), ("test.comp", Some(5), Some(18)));
+    assert_eq!(error.message, "syntax error, unexpected '}', expecting ',' or ';'");
+}
+
+#[test]
+fn nvidia_logs_are_parsed() {
+    let error = error_for("0(LINE) : error C0000: syntax error, unexpected '}'\n");
+    assert_eq!((error.file, error.line, error.column), ("test.comp", Some(5), None));
+    assert_eq!(error.message, "syntax error, unexpected '}'");
+}
+
+#[test]
+fn amd_logs_are_parsed() {
+    let error = error_for("WARNING: 0:2: unused\nERROR: 0:LINE: '}' : syntax error\n");
+    assert_eq!((error.file, error.line), ("test.comp", Some(5)));
+    assert_eq!(error.message, "'}' : syntax error");
+}
+
+#[test]
+fn display_shows_the_source_with_a_caret() {
+    let shown = error_for("0:LINE(5): error: syntax error").to_string();
+    assert_eq!(shown, "test.comp:5:5: compute shader error: syntax error\n\
+                       3 | \n\
+                       4 | void main() {\n\
+                       5 |     float x = 1.0\n  \
+                         |     ^\n\
+                       6 | }");
+}
+
+#[test]
+fn unknown_includes_are_errors() {
+    let file = ShaderFile::new("#version 460 core\n#include \"missing.glsl\"\n", file!(), "test.comp");
+    let error = preprocess(file, &[]).unwrap_err();
+    assert_eq!((error.stage, error.line), (ShaderStage::Preprocess, Some(2)));
+}
@@ -0,0 +1,38 @@
+use hort_mc::world::{World, AIR};
+
+#[test]
+fn blocks_can_be_read_back() {
+    let mut world = World::default();
+    world.set_block(-1, -3, -1, 2);
+    world.set_block(17, 40, -33, 3);
+
+    assert_eq!(world.block_at(-1, -3, -1), 2);
+    assert_eq!(world.block_at(17, 40, -33), 3);
+    assert_eq!(world.block_at(-1, -3, 0), AIR);
+    assert_eq!(world.block_at(1000, 0, 1000), AIR);
+}
+
+#[test]
+fn block_count_ignores_air() {
+    let mut world = World::default();
+    world.set_block(0, 0, 0, 1);
+    world.set_block(1, 0, 0, 1);
+    world.set_block(1, 0, 0, AIR);
+    world.set_block(2, 0, 0, AIR);
+
+    let section = world.chunks.values().next().unwrap().sections.get(&0).unwrap();
+    assert_eq!(section.block_count, 1);
+}
+
+#[test]
+fn bounds_cover_every_block() {
+    assert_eq!(World::default().bounds(), None);
+
+    let mut world = World::default();
+    world.set_block(-17, 3, 5, 1);
+    world.set_block(2, -1, 40, 2);
+    world.set_block(30, 0, 0, 1);
+    world.set_block(30, 0, 0, AIR);
+
+    assert_eq!(world.bounds(), Some(((-17, -1, 5), (20, 5, 36))));
+}